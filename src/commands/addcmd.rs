@@ -1,11 +1,10 @@
-use super::Command;
+use super::{run_scoped, Command, CommandContext, Response, Role};
 use crate::commands::registry::is_builtin;
-use crate::database::sqlite;
-use crate::helpers::has_at_least_n_args;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::Storage;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
 use crate::register_command;
 
-use regex::Regex;
-
 use async_trait::async_trait;
 
 #[derive(Default)]
@@ -17,28 +16,49 @@ impl Command for AddCmd {
         "addcmd"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Adds a custom command, or replaces the response of an existing one."
+    }
+
+    fn usage(&self) -> &'static str {
+        "addcmd [--global] <name> <response>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &[
+            "addcmd hug {sender} hugs {touser}",
+            "addcmd --global discord Join us at discord.gg",
+        ]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match parse_args(args, sender) {
-            Ok((name, response)) => handle_command(name, response, sender),
-            Err(msg) => msg,
-        }
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "addcmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok((name, response)) => handle_command(store, name, response, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
     }
 }
 
 fn parse_args<'a>(args: &'a str, sender: &str) -> Result<(&'a str, &'a str), String> {
     if !has_at_least_n_args(args, 2) {
-        return Err(format!("@{sender} USAGE: addcmd <name> <response>"));
+        return Err(format!("@{sender} USAGE: {}", AddCmd.usage()));
     }
 
     let mut parts = args.splitn(2, ' ');
     let name = parts.next().unwrap_or("");
     let response = parts.next().unwrap_or("").trim();
 
-    if !is_valid_name(name) {
+    if !is_valid_command_name(name) {
         log::error!("{sender} tried to add an invalid command name: {name}");
         return Err("Command name can only contain letters and numbers.".to_string());
     }
@@ -46,29 +66,43 @@ fn parse_args<'a>(args: &'a str, sender: &str) -> Result<(&'a str, &'a str), Str
     Ok((name, response))
 }
 
-fn is_valid_name(name: &str) -> bool {
-    Regex::new(r"^[a-zA-Z0-9]+$").unwrap().is_match(name)
-}
-
-fn handle_command(name: &str, response: &str, sender: &str) -> String {
+fn handle_command(
+    store: &dyn Storage,
+    name: &str,
+    response: &str,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
     if is_builtin(name) {
         log::error!(
             "{sender} tried to add a command that already exists as a built-in command: {name}"
         );
 
-        return String::from(
+        return Ok(String::from(
             "The command you are trying to add already exists as a built-in command.",
-        );
+        ));
+    }
+
+    let alias = match scope {
+        Some(channel) => store.resolve_alias(name, channel)?,
+        None => store.get_alias(name, None)?,
+    };
+
+    if let Some(target) = alias {
+        return Ok(format!(
+            "{name} is an alias of {target}. Remove it with alias del first."
+        ));
     }
 
-    if sqlite::get_command_response(name).is_ok() {
-        sqlite::update_command_response(name, response);
-        log::info!("{sender} updated an existing command: {name} -> {response}");
-        return String::from("Command already exists. It was updated with the new response.");
+    if store.create_command(name, response, scope, sender)? {
+        return Ok(String::from("Command added!"));
     }
 
-    sqlite::create_command(name, response);
-    String::from("Command added!")
+    store.update_command_response(name, scope, response, sender)?;
+    log::info!("{sender} updated an existing command: {name} -> {response}");
+    Ok(String::from(
+        "Command already exists. It was updated with the new response.",
+    ))
 }
 
 register_command!(AddCmd);
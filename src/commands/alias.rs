@@ -0,0 +1,125 @@
+use super::{run_scoped, Command, CommandContext, Response, Role};
+use crate::commands::registry::is_builtin;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::Storage;
+use crate::helpers::is_valid_command_name;
+use crate::register_command;
+
+use async_trait::async_trait;
+
+const USAGE: &str = "alias [--global] add <alias> <command> | alias [--global] del <alias>";
+
+#[derive(Default)]
+pub struct Alias;
+
+#[async_trait]
+impl Command for Alias {
+    fn name(&self) -> &'static str {
+        "alias"
+    }
+
+    fn description(&self) -> &'static str {
+        "Manages other names for custom commands."
+    }
+
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["alias add dc discord", "alias del dc"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "alias",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok(action) => handle_command(store, action, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
+    }
+}
+
+enum Action<'a> {
+    Add { alias: &'a str, target: &'a str },
+    Del { alias: &'a str },
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<Action<'a>, String> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+
+    match parts[..] {
+        ["add", alias, target] => {
+            validate_name(alias, sender)?;
+            validate_name(target, sender)?;
+            Ok(Action::Add { alias, target })
+        }
+        ["del", alias] => {
+            validate_name(alias, sender)?;
+            Ok(Action::Del { alias })
+        }
+        _ => Err(format!("@{sender} USAGE: {USAGE}")),
+    }
+}
+
+fn validate_name(name: &str, sender: &str) -> Result<(), String> {
+    if !is_valid_command_name(name) {
+        log::error!("{sender} tried to use an invalid alias or command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    Ok(())
+}
+
+fn handle_command(
+    store: &dyn Storage,
+    action: Action,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
+    match action {
+        Action::Add { alias, target } => {
+            if is_builtin(alias) {
+                log::error!(
+                    "{sender} tried to add an alias that collides with a built-in command: {alias}"
+                );
+                return Ok(format!("{alias} is already a built-in command."));
+            }
+
+            if store.command_exists(alias, scope)? {
+                return Ok(format!("{alias} is already a custom command."));
+            }
+
+            let target_exists = match scope {
+                Some(channel) => store.get_command(target, channel)?.is_some(),
+                None => store.command_exists(target, None)?,
+            };
+
+            if !target_exists {
+                return Ok(format!("Command {target} does not exist."));
+            }
+
+            store.create_alias(alias, target, scope)?;
+            log::info!("{sender} added the alias {alias} -> {target}");
+            Ok(format!("{alias} now runs {target}."))
+        }
+        Action::Del { alias } => {
+            if !store.delete_alias(alias, scope)? {
+                return Ok(format!("Alias {alias} does not exist."));
+            }
+
+            log::info!("{sender} deleted the alias {alias}");
+            Ok(String::from("Alias deleted!"))
+        }
+    }
+}
+
+register_command!(Alias);
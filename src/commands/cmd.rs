@@ -1,10 +1,15 @@
-use super::Command;
+use super::{Command, CommandContext, Response, Role};
+use crate::commands::listing;
 use crate::commands::registry::Registry;
-use crate::database::sqlite;
+use crate::database::store::CommandStore;
+use crate::helpers::MAX_TWITCH_CHAT_MESSAGE_LENGTH;
 use crate::register_command;
 
 use async_trait::async_trait;
 
+/// Room left in each page for the " (page 10/10)" suffix.
+const PAGE_SUFFIX_LENGTH: usize = 16;
+
 #[derive(Default)]
 pub struct Commands;
 
@@ -14,44 +19,144 @@ impl Command for Commands {
         "commands"
     }
 
-    async fn execute(&self, _args: &str, _sender: &str) -> String {
-        let db_commands = fetch_db_commands();
-        let builtin_commands = format_builtin_commands();
+    fn description(&self) -> &'static str {
+        "Lists the commands you can use here, or links to the generated list when one is set up."
+    }
+
+    fn usage(&self) -> &'static str {
+        "commands [filter] [page]"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["commands 2", "commands mtg"]
+    }
+
+    fn aliases(&self) -> &'static [&'static str] {
+        &["cmds", "comandos"]
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let (filter, page) = parse_args(&ctx.args);
 
-        combine_commands(builtin_commands, db_commands)
+        if filter.is_none() && page.is_none() {
+            if let Some(url) = ctx.config().list_url_for(ctx.channel()) {
+                // Write the list first, so the link never points at a page
+                // that is missing or out of date.
+                let (config, channel) = (ctx.services.config.clone(), ctx.channel().to_owned());
+                let written = ctx
+                    .store()
+                    .run(move |store| listing::write(store, &config, &channel))
+                    .await;
+
+                match written {
+                    Ok(()) => {
+                        return Response::Message(format!("Commands for this channel: {url}"))
+                    }
+                    Err(e) => log::error!(
+                        "Could not write the command list for #{}, listing in chat: {e}",
+                        ctx.channel()
+                    ),
+                }
+            }
+        }
+
+        let mut names = builtin_commands(ctx.role);
+        let (channel, role) = (ctx.channel().to_owned(), ctx.role);
+        names.extend(
+            ctx.store()
+                .run(move |store| fetch_db_commands(store, &channel, role))
+                .await,
+        );
+
+        names.sort_by_cached_key(|name| name.to_lowercase());
+        names.dedup();
+
+        if let Some(filter) = filter {
+            let filter = filter.to_lowercase();
+            names.retain(|name| name.to_lowercase().contains(&filter));
+        }
+
+        Response::from(handle_command(names, filter, page.unwrap_or(1)))
     }
 }
 
-fn fetch_db_commands() -> String {
-    match sqlite::get_commands() {
+fn parse_args(args: &str) -> (Option<&str>, Option<usize>) {
+    let mut filter = None;
+    let mut page = None;
+
+    for word in args.split_whitespace() {
+        match word.parse::<usize>() {
+            Ok(number) if page.is_none() => page = Some(number),
+            _ if filter.is_none() => filter = Some(word),
+            _ => (),
+        }
+    }
+
+    (filter, page)
+}
+
+fn fetch_db_commands(store: &dyn CommandStore, channel: &str, role: Role) -> Vec<String> {
+    match store.get_commands(channel) {
         Ok(commands) => commands
-            .iter()
-            .map(|(name, _)| name.clone())
-            .collect::<Vec<_>>()
-            .join(", "),
+            .into_iter()
+            .filter(|(_, required)| *required <= role)
+            .map(|(name, _)| name)
+            .collect(),
         Err(e) => {
             log::error!("Failed to retrieve commands: {e}");
-            String::new()
+            Vec::new()
         }
     }
 }
 
-fn format_builtin_commands() -> String {
+fn builtin_commands(role: Role) -> Vec<String> {
     Registry::all()
         .iter()
-        .filter(|cmd| !cmd.requires_trust())
+        .filter(|cmd| cmd.required_role() <= role)
         .map(|cmd| cmd.name().to_string())
-        .collect::<Vec<_>>()
-        .join(", ")
+        .collect()
 }
 
-fn combine_commands(builtin: String, db: String) -> String {
-    match (builtin.is_empty(), db.is_empty()) {
-        (false, false) => format!("{builtin}, {db}"),
-        (false, true) => builtin,
-        (true, false) => db,
-        (true, true) => String::new(),
+fn handle_command(names: Vec<String>, filter: Option<&str>, page: usize) -> String {
+    if names.is_empty() {
+        return match filter {
+            Some(filter) => format!("No commands match {filter}."),
+            None => String::new(),
+        };
     }
+
+    let pages = paginate(names, MAX_TWITCH_CHAT_MESSAGE_LENGTH - PAGE_SUFFIX_LENGTH);
+    let total = pages.len();
+
+    match pages.get(page.max(1) - 1) {
+        Some(list) if total == 1 => list.clone(),
+        Some(list) => format!("{list} (page {}/{total})", page.max(1)),
+        None => format!("There are only {total} page(s) of commands."),
+    }
+}
+
+/// Joins `names` into comma-separated pages that are at most `width`
+/// characters long.
+fn paginate(names: Vec<String>, width: usize) -> Vec<String> {
+    let mut pages: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for name in names {
+        if !current.is_empty() && current.chars().count() + 2 + name.chars().count() > width {
+            pages.push(std::mem::take(&mut current));
+        }
+
+        if !current.is_empty() {
+            current.push_str(", ");
+        }
+        current.push_str(&name);
+    }
+
+    if !current.is_empty() {
+        pages.push(current);
+    }
+
+    pages
 }
 
 register_command!(Commands);
@@ -0,0 +1,315 @@
+use super::mode::ResponseMode;
+use super::{run_scoped, Command, CommandContext, Cooldown, Response, Role};
+use crate::commands::registry::is_builtin;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::CommandStore;
+use crate::helpers::{is_valid_command_name, truncate};
+use crate::register_command;
+
+use async_trait::async_trait;
+
+const USAGE: &str = "cmd [--global] role <name> <role> | cooldown <name> <seconds> [user seconds] | addresp <name> <response> | delresp <name> <number> | listresp <name> | mode <name> <random|rotate|weighted> | weight <name> <number> <weight> | describe <name> [description]";
+
+const RESPONSE_PREVIEW_LENGTH: usize = 60;
+
+#[derive(Default)]
+pub struct CmdEdit;
+
+#[async_trait]
+impl Command for CmdEdit {
+    fn name(&self) -> &'static str {
+        "cmd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Changes the settings and responses of a custom command."
+    }
+
+    fn usage(&self) -> &'static str {
+        USAGE
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &[
+            "cmd role rules mod",
+            "cmd cooldown hug 10 60",
+            "cmd addresp tip Stretch a bit",
+            "cmd mode tip rotate",
+            "cmd describe discord Our Discord server",
+        ]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "cmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok(action) => handle_command(store, action, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
+    }
+}
+
+enum Action<'a> {
+    Role {
+        name: &'a str,
+        role: Role,
+    },
+    Cooldown {
+        name: &'a str,
+        cooldown: Cooldown,
+    },
+    AddResp {
+        name: &'a str,
+        response: &'a str,
+    },
+    DelResp {
+        name: &'a str,
+        number: usize,
+    },
+    ListResp {
+        name: &'a str,
+    },
+    Mode {
+        name: &'a str,
+        mode: ResponseMode,
+    },
+    Weight {
+        name: &'a str,
+        number: usize,
+        weight: u32,
+    },
+    Describe {
+        name: &'a str,
+        description: Option<&'a str>,
+    },
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<Action<'a>, String> {
+    let parts: Vec<&str> = args.split_whitespace().collect();
+
+    match parts[..] {
+        ["role", name, role] => {
+            validate_name(name, sender)?;
+            Ok(Action::Role {
+                name,
+                role: role.parse()?,
+            })
+        }
+        ["cooldown", name, global] => {
+            validate_name(name, sender)?;
+            Ok(Action::Cooldown {
+                name,
+                cooldown: Cooldown::from_secs(parse_seconds(global)?, 0),
+            })
+        }
+        ["cooldown", name, global, per_user] => {
+            validate_name(name, sender)?;
+            Ok(Action::Cooldown {
+                name,
+                cooldown: Cooldown::from_secs(parse_seconds(global)?, parse_seconds(per_user)?),
+            })
+        }
+        ["addresp", name, _, ..] => {
+            validate_name(name, sender)?;
+            Ok(Action::AddResp {
+                name,
+                response: skip_words(args, 2),
+            })
+        }
+        ["delresp", name, number] => {
+            validate_name(name, sender)?;
+            Ok(Action::DelResp {
+                name,
+                number: parse_number(number)?,
+            })
+        }
+        ["listresp", name] => {
+            validate_name(name, sender)?;
+            Ok(Action::ListResp { name })
+        }
+        ["mode", name, mode] => {
+            validate_name(name, sender)?;
+            Ok(Action::Mode {
+                name,
+                mode: mode.parse()?,
+            })
+        }
+        ["weight", name, number, weight] => {
+            validate_name(name, sender)?;
+            Ok(Action::Weight {
+                name,
+                number: parse_number(number)?,
+                weight: weight
+                    .parse()
+                    .map_err(|_| format!("Weights are whole numbers, got `{weight}`."))?,
+            })
+        }
+        ["describe", name, ..] => {
+            validate_name(name, sender)?;
+            let description = skip_words(args, 2);
+            Ok(Action::Describe {
+                name,
+                description: (!description.is_empty()).then_some(description),
+            })
+        }
+        _ => Err(format!("@{sender} USAGE: {USAGE}")),
+    }
+}
+
+/// Returns what is left of `args` after its first `n` words.
+fn skip_words(args: &str, n: usize) -> &str {
+    (0..n)
+        .fold(args.trim_start(), |rest, _| {
+            rest.split_once(char::is_whitespace)
+                .map_or("", |(_, rest)| rest.trim_start())
+        })
+        .trim_end()
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .trim_start_matches('#')
+        .parse()
+        .map_err(|_| format!("Response numbers are whole numbers, got `{value}`."))
+}
+
+fn parse_seconds(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Cooldowns are whole seconds, got `{value}`."))
+}
+
+fn validate_name(name: &str, sender: &str) -> Result<(), String> {
+    if !is_valid_command_name(name) {
+        log::error!("{sender} tried to edit an invalid command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    Ok(())
+}
+
+fn handle_command(
+    store: &dyn CommandStore,
+    action: Action,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
+    let reply = match action {
+        Action::Role { name, role } => {
+            if is_builtin(name) {
+                return Ok(String::from("Built-in commands declare their own role."));
+            }
+
+            if !store.set_command_role(name, scope, role)? {
+                return Ok(format!("Command {name} does not exist."));
+            }
+
+            log::info!("{sender} set the role of {name} to {role}");
+            format!("{name} now requires the {role} role.")
+        }
+        Action::Cooldown { name, cooldown } => {
+            if is_builtin(name) {
+                return Ok(String::from(
+                    "Built-in commands declare their own cooldown.",
+                ));
+            }
+
+            if !store.set_command_cooldown(name, scope, cooldown)? {
+                return Ok(format!("Command {name} does not exist."));
+            }
+
+            let global = cooldown.global.as_secs();
+            let per_user = cooldown.per_user.as_secs();
+            log::info!(
+                "{sender} set the cooldown of {name} to {global}s global, {per_user}s per user"
+            );
+            format!("{name} now has a {global}s cooldown and a {per_user}s cooldown per user.")
+        }
+        Action::AddResp { name, response } => match store.add_response(name, scope, response)? {
+            Some(number) => {
+                log::info!("{sender} added response #{number} to {name}: {response}");
+                format!("Added response #{number} to {name}.")
+            }
+            None => format!("Command {name} does not exist."),
+        },
+        Action::DelResp { name, number: 1 } => {
+            format!("Response #1 is the main response of {name}. Use updcmd to change it.")
+        }
+        Action::DelResp { name, number } => {
+            if !store.delete_response(name, scope, number)? {
+                return Ok(format!("{name} has no response #{number}."));
+            }
+
+            log::info!("{sender} deleted response #{number} of {name}");
+            format!("Deleted response #{number} of {name}.")
+        }
+        Action::ListResp { name } => match store.list_responses(name, scope)? {
+            Some((mode, responses)) => {
+                let responses = responses
+                    .iter()
+                    .enumerate()
+                    .map(|(i, response)| {
+                        let number = i + 1;
+                        let weight = match mode {
+                            ResponseMode::Weighted => format!(" (weight {})", response.weight),
+                            _ => String::new(),
+                        };
+                        let text = truncate(&response.text, RESPONSE_PREVIEW_LENGTH);
+                        format!("#{number}{weight}: {text}")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+
+                format!("{name} ({mode}): {responses}")
+            }
+            None => format!("Command {name} does not exist."),
+        },
+        Action::Mode { name, mode } => {
+            if !store.set_response_mode(name, scope, mode)? {
+                return Ok(format!("Command {name} does not exist."));
+            }
+
+            log::info!("{sender} set the response mode of {name} to {mode}");
+            format!("{name} now picks responses in {mode} mode.")
+        }
+        Action::Weight {
+            name,
+            number,
+            weight,
+        } => {
+            if !store.set_response_weight(name, scope, number, weight)? {
+                return Ok(format!("{name} has no response #{number}."));
+            }
+
+            log::info!("{sender} set the weight of response #{number} of {name} to {weight}");
+            format!("Response #{number} of {name} now has a weight of {weight}.")
+        }
+        Action::Describe { name, description } => {
+            if is_builtin(name) {
+                return Ok(String::from("Built-in commands describe themselves."));
+            }
+
+            if !store.set_command_description(name, scope, description)? {
+                return Ok(format!("Command {name} does not exist."));
+            }
+
+            log::info!("{sender} set the description of {name} to {description:?}");
+            match description {
+                Some(_) => format!("{name} now has a description."),
+                None => format!("{name} no longer has a description."),
+            }
+        }
+    };
+
+    Ok(reply)
+}
+
+register_command!(CmdEdit);
@@ -0,0 +1,108 @@
+use super::{run_scoped, Command, CommandContext, Response, Role};
+use crate::database::error::{Access, StoreError};
+use crate::database::store::{CommandStore, Revision};
+use crate::helpers::{has_at_least_n_args, is_valid_command_name, truncate};
+use crate::register_command;
+
+use async_trait::async_trait;
+
+const REVISIONS_SHOWN: usize = 5;
+const RESPONSE_PREVIEW_LENGTH: usize = 40;
+
+#[derive(Default)]
+pub struct CmdHistory;
+
+#[async_trait]
+impl Command for CmdHistory {
+    fn name(&self) -> &'static str {
+        "cmdhistory"
+    }
+
+    fn description(&self) -> &'static str {
+        "Lists the latest changes to a custom command."
+    }
+
+    fn usage(&self) -> &'static str {
+        "cmdhistory [--global] <name>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["cmdhistory rules"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "cmdhistory",
+            Access::Read,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok(name) => handle_command(store, name, scope),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
+    }
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
+    if !has_at_least_n_args(args, 1) {
+        return Err(format!("@{sender} USAGE: {}", CmdHistory.usage()));
+    }
+
+    let name: &str = args.split_whitespace().next().unwrap();
+
+    if !is_valid_command_name(name) {
+        log::error!("{sender} asked for the history of an invalid command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    Ok(name)
+}
+
+fn handle_command(
+    store: &dyn CommandStore,
+    name: &str,
+    scope: Option<&str>,
+) -> Result<String, StoreError> {
+    let revisions = store.get_revisions(name, scope, REVISIONS_SHOWN)?;
+
+    if revisions.is_empty() {
+        return Ok(format!("{name} has no recorded changes."));
+    }
+
+    let entries = revisions
+        .iter()
+        .map(format_revision)
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    Ok(format!("{name}: {entries}"))
+}
+
+fn format_revision(revision: &Revision) -> String {
+    let Revision {
+        number,
+        action,
+        author,
+        created_at,
+        ..
+    } = revision;
+
+    match (&revision.new_response, &revision.old_response) {
+        (Some(response), _) => format!(
+            "#{number} {action} by {author} at {created_at}: \"{}\"",
+            truncate(response, RESPONSE_PREVIEW_LENGTH)
+        ),
+        (None, Some(response)) => format!(
+            "#{number} {action} by {author} at {created_at}, was \"{}\"",
+            truncate(response, RESPONSE_PREVIEW_LENGTH)
+        ),
+        (None, None) => format!("#{number} {action} by {author} at {created_at}"),
+    }
+}
+
+register_command!(CmdHistory);
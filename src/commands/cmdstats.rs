@@ -0,0 +1,105 @@
+use super::{store_failure, Command, CommandContext, Response};
+use crate::commands::registry::Registry;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::Storage;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
+use crate::register_command;
+
+use async_trait::async_trait;
+
+#[derive(Default)]
+pub struct CmdStats;
+
+#[async_trait]
+impl Command for CmdStats {
+    fn name(&self) -> &'static str {
+        "cmdstats"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shows how often a command was used and who used it last."
+    }
+
+    fn usage(&self) -> &'static str {
+        "cmdstats <name>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["cmdstats hug"]
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let sender = ctx.sender().to_owned();
+        let channel = ctx.channel().to_owned();
+        let args = ctx.args.clone();
+
+        let reply = ctx
+            .store()
+            .run(move |store| match parse_args(&args, &sender) {
+                Ok(name) => handle_command(store, name, &channel)
+                    .unwrap_or_else(|e| store_failure("cmdstats", &sender, Access::Read, e)),
+                Err(msg) => msg,
+            })
+            .await;
+
+        Response::Message(reply)
+    }
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
+    if !has_at_least_n_args(args, 1) {
+        return Err(format!("@{sender} USAGE: {}", CmdStats.usage()));
+    }
+
+    let name: &str = args.split_whitespace().next().unwrap();
+
+    if !is_valid_command_name(name) {
+        log::error!("{sender} asked for stats of an invalid command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    Ok(name)
+}
+
+fn handle_command(store: &dyn Storage, name: &str, channel: &str) -> Result<String, StoreError> {
+    // Uses are counted under the name a command was resolved to, so aliases
+    // have to be looked up the same way.
+    let (name, builtin) = match Registry::get(name) {
+        Some(command) => (command.name().to_string(), true),
+        None => (
+            store
+                .resolve_alias(name, channel)?
+                .unwrap_or_else(|| name.to_string()),
+            false,
+        ),
+    };
+
+    let created = if builtin {
+        String::from("It is a built-in command.")
+    } else {
+        match store.get_command(&name, channel)? {
+            Some(command) => format!("Created on {}.", date(&command.created_at)),
+            None => return Ok(format!("Command {name} does not exist.")),
+        }
+    };
+
+    let reply = match store.get_usage(&name, channel)? {
+        Some(usage) => format!(
+            "{name} was used {} {}, last by @{} on {}. {created}",
+            usage.count,
+            if usage.count == 1 { "time" } else { "times" },
+            usage.last_caller,
+            usage.last_used_at,
+        ),
+        None => format!("{name} has not been used here yet. {created}"),
+    };
+
+    Ok(reply)
+}
+
+/// Drops the time from a SQLite `datetime`, leaving `YYYY-MM-DD`.
+fn date(datetime: &str) -> &str {
+    datetime.split_whitespace().next().unwrap_or(datetime)
+}
+
+register_command!(CmdStats);
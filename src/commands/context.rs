@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use twitch_irc::message::PrivmsgMessage;
+
+use super::cooldown::Cooldowns;
+use super::Role;
+use crate::config::Config;
+use crate::database::store::Store;
+
+/// Long-lived services shared by the dispatcher and every command.
+pub struct Services {
+    pub store: Store,
+    pub http: reqwest::Client,
+    pub config: Arc<Config>,
+    pub cooldowns: Cooldowns,
+    /// Makes every random pick repeat, for tests. `None` seeds from entropy.
+    seed: Option<u64>,
+}
+
+impl Services {
+    pub fn new(store: Store, config: Config) -> Services {
+        Services {
+            store,
+            http: reqwest::Client::new(),
+            config: Arc::new(config),
+            cooldowns: Cooldowns::default(),
+            seed: None,
+        }
+    }
+
+    /// The same services, but with random picks fixed by `seed`.
+    #[cfg(test)]
+    pub fn seeded(self, seed: u64) -> Services {
+        Services {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+/// Everything a command gets to see when it runs: the arguments, the chat
+/// message that triggered it and the shared services.
+pub struct CommandContext {
+    pub args: String,
+    pub message: PrivmsgMessage,
+    pub role: Role,
+    pub services: Arc<Services>,
+    /// `{cmd:...}` macros run so far for the triggering message, shared by
+    /// every command the macros run.
+    macros_run: Arc<AtomicUsize>,
+}
+
+impl CommandContext {
+    pub async fn new(
+        args: String,
+        message: PrivmsgMessage,
+        services: Arc<Services>,
+    ) -> CommandContext {
+        CommandContext {
+            args,
+            role: Role::of(&message, &services).await,
+            message,
+            services,
+            macros_run: Arc::default(),
+        }
+    }
+
+    /// The same caller running another command with `args`.
+    pub fn with_args(&self, args: String) -> CommandContext {
+        CommandContext {
+            args,
+            message: self.message.clone(),
+            role: self.role,
+            services: self.services.clone(),
+            macros_run: self.macros_run.clone(),
+        }
+    }
+
+    /// Counts another macro run for the triggering message and returns how
+    /// many have run, this one included.
+    pub fn count_macro(&self) -> usize {
+        self.macros_run.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub fn sender(&self) -> &str {
+        &self.message.sender.login
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.message.channel_login
+    }
+
+    /// Whether the sender may change global commands, which answer in every
+    /// channel: the broadcaster, or a user in the top-level trusted list.
+    /// Trust that only holds in this channel is not enough.
+    pub fn can_change_global(&self) -> bool {
+        self.role == Role::Broadcaster
+            || self
+                .config()
+                .trusted_users
+                .iter()
+                .any(|user| user == self.sender())
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.services.store
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.services.config
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.services.http
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a command stays unavailable after it runs, across the whole
+/// channel and for the user who ran it. Zero means no cooldown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cooldown {
+    pub global: Duration,
+    pub per_user: Duration,
+}
+
+impl Cooldown {
+    pub const fn from_secs(global: u64, per_user: u64) -> Cooldown {
+        Cooldown {
+            global: Duration::from_secs(global),
+            per_user: Duration::from_secs(per_user),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.global.is_zero() && self.per_user.is_zero()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    channel: String,
+    command: String,
+    user: Option<String>,
+}
+
+/// In-memory record of when each command was last used, per channel.
+#[derive(Default)]
+pub struct Cooldowns {
+    last_used: Mutex<HashMap<Key, Instant>>,
+}
+
+impl Cooldowns {
+    /// Records a use of `command` by `user` if it is off cooldown. Otherwise
+    /// returns how long is left before it can run again.
+    pub fn try_use(
+        &self,
+        channel: &str,
+        command: &str,
+        user: &str,
+        cooldown: Cooldown,
+    ) -> Option<Duration> {
+        if cooldown.is_zero() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let global = Key {
+            channel: String::from(channel),
+            command: String::from(command),
+            user: None,
+        };
+        let per_user = Key {
+            user: Some(String::from(user)),
+            ..global.clone()
+        };
+
+        let mut last_used = self.last_used.lock().unwrap();
+        let remaining = [(&global, cooldown.global), (&per_user, cooldown.per_user)]
+            .into_iter()
+            .filter_map(|(key, length)| {
+                let since = now.duration_since(*last_used.get(key)?);
+                length.checked_sub(since).filter(|left| !left.is_zero())
+            })
+            .max();
+
+        if remaining.is_none() {
+            last_used.insert(global, now);
+            last_used.insert(per_user, now);
+        }
+
+        remaining
+    }
+}
@@ -1,11 +1,10 @@
-use super::Command;
+use super::{run_scoped, Command, CommandContext, Response, Role};
 use crate::commands::registry::is_builtin;
-use crate::database::sqlite;
-use crate::helpers::has_at_least_n_args;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::CommandStore;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
 use crate::register_command;
 
-use regex::Regex;
-
 use async_trait::async_trait;
 
 #[derive(Default)]
@@ -17,26 +16,44 @@ impl Command for DelCmd {
         "delcmd"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Deletes a custom command. It can be brought back with undelcmd."
+    }
+
+    fn usage(&self) -> &'static str {
+        "delcmd [--global] <name>"
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match parse_args(args, sender) {
-            Ok(name) => handle_command(name, sender),
-            Err(msg) => msg,
-        }
+    fn examples(&self) -> &'static [&'static str] {
+        &["delcmd hug"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "delcmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok(name) => handle_command(store, name, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
     }
 }
 
 fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     if !has_at_least_n_args(args, 1) {
-        return Err(format!("@{sender} USAGE: delcmd <name>"));
+        return Err(format!("@{sender} USAGE: {}", DelCmd.usage()));
     }
 
     let name: &str = args.split(' ').collect::<Vec<&str>>()[0];
 
-    if !is_valid_name(name) {
+    if !is_valid_command_name(name) {
         log::error!("{sender} tried to delete an invalid command name: {name}");
         return Err("Command name can only contain letters and numbers.".to_string());
     }
@@ -44,18 +61,24 @@ fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     Ok(name)
 }
 
-fn is_valid_name(name: &str) -> bool {
-    Regex::new(r"^[a-zA-Z0-9]+$").unwrap().is_match(name)
-}
-
-fn handle_command(name: &str, sender: &str) -> String {
+fn handle_command(
+    store: &dyn CommandStore,
+    name: &str,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
     if is_builtin(name) {
         log::error!("{sender} tried to delete a built-in command: {name}");
-        return String::from("The command you are trying to delete already is a built-in command.");
+        return Ok(String::from(
+            "The command you are trying to delete already is a built-in command.",
+        ));
+    }
+
+    if !store.delete_command(name, scope, sender)? {
+        return Ok(format!("Command {name} does not exist."));
     }
 
-    sqlite::delete_command(name);
-    String::from("Command deleted!")
+    Ok(String::from("Command deleted!"))
 }
 
 register_command!(DelCmd);
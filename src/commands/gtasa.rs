@@ -1,4 +1,4 @@
-use super::Command;
+use super::{Command, CommandContext, Response};
 use crate::helpers::has_at_least_n_args;
 use crate::register_command;
 
@@ -15,21 +15,33 @@ impl Command for Gta {
         "gta"
     }
 
-    async fn execute(&self, args: &str, _sender: &str) -> String {
+    fn description(&self) -> &'static str {
+        "Sends a random line of GTA San Andreas mission script, optionally containing a word."
+    }
+
+    fn usage(&self) -> &'static str {
+        "gta [word]"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["gta", "gta grove"]
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
         let lines = match read_lines_from_file("data/main.scm") {
             Ok(lines) => lines,
-            Err(_) => return String::from("Error reading file"),
+            Err(_) => return Response::Message(String::from("Error reading file")),
         };
 
-        let target = if has_at_least_n_args(args, 1) {
-            Some(args.split_whitespace().next().unwrap())
+        let target = if has_at_least_n_args(&ctx.args, 1) {
+            Some(ctx.args.split_whitespace().next().unwrap())
         } else {
             None
         };
 
         match choose_line(&lines, target) {
-            Some(line) => String::from(line),
-            None => String::from(""),
+            Some(line) => Response::Message(String::from(line)),
+            None => Response::None,
         }
     }
 }
@@ -0,0 +1,119 @@
+use super::{store_failure, Command, CommandContext, Response, Role};
+use crate::commands::registry::Registry;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::Storage;
+use crate::helpers::has_at_least_n_args;
+use crate::register_command;
+
+use async_trait::async_trait;
+
+#[derive(Default)]
+pub struct Help;
+
+#[async_trait]
+impl Command for Help {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn description(&self) -> &'static str {
+        "Explains what a command does and how to use it."
+    }
+
+    fn usage(&self) -> &'static str {
+        "help <command>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["help addcmd", "help hug"]
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let sender = ctx.sender();
+
+        if !has_at_least_n_args(&ctx.args, 1) {
+            return Response::Message(format!("@{sender} USAGE: {}", Help.usage()));
+        }
+
+        let prefix = ctx.config().prefix_for(ctx.channel());
+        let name = ctx.args.split_whitespace().next().unwrap();
+        let name = name.strip_prefix(prefix).unwrap_or(name);
+
+        let reply = match Registry::get(name) {
+            Some(command) => describe_builtin(command.as_ref(), prefix),
+            None => {
+                let (name, channel, prefix) =
+                    (name.to_owned(), ctx.channel().to_owned(), prefix.to_owned());
+                let sender = ctx.sender().to_owned();
+                ctx.store()
+                    .run(move |store| {
+                        describe_custom(store, &name, &channel, &prefix)
+                            .unwrap_or_else(|e| store_failure("help", &sender, Access::Read, e))
+                    })
+                    .await
+            }
+        };
+
+        Response::Message(reply)
+    }
+}
+
+fn describe_builtin(command: &(dyn Command + Send + Sync), prefix: &str) -> String {
+    let mut reply = format!("{prefix}{}", command.usage());
+
+    if !command.description().is_empty() {
+        reply.push_str(&format!(" — {}", command.description()));
+    }
+
+    if command.required_role() != Role::Everyone {
+        reply.push_str(&format!(" Requires {}.", command.required_role()));
+    }
+
+    if !command.aliases().is_empty() {
+        let aliases: Vec<String> = command
+            .aliases()
+            .iter()
+            .map(|alias| format!("{prefix}{alias}"))
+            .collect();
+        reply.push_str(&format!(" Also: {}.", aliases.join(", ")));
+    }
+
+    if !command.examples().is_empty() {
+        let examples: Vec<String> = command
+            .examples()
+            .iter()
+            .map(|example| format!("{prefix}{example}"))
+            .collect();
+        reply.push_str(&format!(" Examples: {}", examples.join(" | ")));
+    }
+
+    reply
+}
+
+fn describe_custom(
+    store: &dyn Storage,
+    name: &str,
+    channel: &str,
+    prefix: &str,
+) -> Result<String, StoreError> {
+    let target = store
+        .resolve_alias(name, channel)?
+        .unwrap_or_else(|| name.to_string());
+
+    let Some(command) = store.get_command(&target, channel)? else {
+        return Ok(format!("Command {name} does not exist."));
+    };
+
+    let mut reply = match command.description {
+        Some(description) => format!("{prefix}{target} — {description}"),
+        None => format!("{prefix}{target} is a custom command."),
+    };
+
+    if command.role != Role::Everyone {
+        reply.push_str(&format!(" Requires {}.", command.role));
+    }
+
+    Ok(reply)
+}
+
+register_command!(Help);
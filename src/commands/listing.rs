@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::registry::Registry;
+use super::Role;
+use crate::config::Config;
+use crate::database::store::CommandStore;
+
+/// One row of the generated command list.
+struct Entry {
+    name: String,
+    role: Role,
+    description: String,
+}
+
+/// Writes the HTML command list of `channel` to `command.list_path`, which
+/// `!commands` links to through `command.list_url`. Does nothing when no
+/// path is configured.
+pub fn write(store: &dyn CommandStore, config: &Config, channel: &str) -> Result<()> {
+    let Some(path) = config.list_path_for(channel) else {
+        return Ok(());
+    };
+
+    let page = render(
+        &entries(store, channel)?,
+        config.prefix_for(channel),
+        channel,
+    );
+    let path = Path::new(&path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Replace the page in one step, so it is never served half written.
+    let partial = path.with_extension("partial");
+    fs::write(&partial, page)?;
+    fs::rename(&partial, path)?;
+
+    Ok(())
+}
+
+/// Rewrites the list of every channel the scope affects: one channel, or
+/// all joined channels for the global scope.
+pub fn refresh(store: &dyn CommandStore, config: &Config, scope: Option<&str>) {
+    let channels = match scope {
+        Some(channel) => vec![channel],
+        None => config.channels.iter().map(String::as_str).collect(),
+    };
+
+    for channel in channels {
+        if let Err(e) = write(store, config, channel) {
+            log::error!("Could not write the command list for #{channel}: {e}");
+        }
+    }
+}
+
+/// Every builtin and custom command available in `channel`, sorted by name.
+fn entries(store: &dyn CommandStore, channel: &str) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = Registry::all()
+        .iter()
+        .map(|command| Entry {
+            name: command.name().to_string(),
+            role: command.required_role(),
+            description: command.description().to_string(),
+        })
+        .collect();
+
+    for (name, role) in store.get_commands(channel)? {
+        let description = store
+            .get_command(&name, channel)?
+            .and_then(|command| command.description)
+            .unwrap_or_default();
+        entries.push(Entry {
+            name,
+            role,
+            description,
+        });
+    }
+
+    entries.sort_by_cached_key(|entry| entry.name.to_lowercase());
+    entries.dedup_by(|a, b| a.name == b.name);
+
+    Ok(entries)
+}
+
+fn render(entries: &[Entry], prefix: &str, channel: &str) -> String {
+    let channel = escape(channel);
+    let mut page = format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>Commands for #{channel}</title>\n\
+         </head>\n\
+         <body>\n\
+         <h1>Commands for #{channel}</h1>\n\
+         <table>\n\
+         <tr><th>Command</th><th>Who can use it</th><th>Description</th></tr>\n"
+    );
+
+    for entry in entries {
+        page.push_str(&format!(
+            "<tr><td>{}{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape(prefix),
+            escape(&entry.name),
+            entry.role,
+            escape(&entry.description)
+        ));
+    }
+
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
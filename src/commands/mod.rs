@@ -1,25 +1,127 @@
 pub mod addcmd;
+pub mod alias;
 pub mod cmd;
+pub mod cmdedit;
+pub mod cmdhistory;
+pub mod cmdstats;
 pub mod delcmd;
 pub mod gtasa;
+pub mod help;
 pub mod node;
 pub mod odds;
 pub mod ping;
+pub mod revertcmd;
 pub mod trust;
+pub mod undelcmd;
 pub mod untrust;
 pub mod updcmd;
 pub mod wttr;
 
+pub mod context;
+pub mod cooldown;
+pub mod listing;
+pub mod mode;
 pub mod registry;
+pub mod response;
+pub mod role;
+pub mod template;
 use async_trait::async_trait;
 
+use crate::database::error::{Access, StoreError};
+use crate::database::store::Storage;
+use crate::helpers::take_flag;
+
+pub use context::CommandContext;
+pub use cooldown::Cooldown;
+pub use response::Response;
+pub use role::Role;
+
 #[async_trait]
 pub trait Command {
     fn name(&self) -> &'static str;
 
-    fn requires_trust(&self) -> bool {
-        false
+    /// Other names the command answers to.
+    fn aliases(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// One line on what the command does, shown by `help`.
+    fn description(&self) -> &'static str {
+        ""
+    }
+
+    /// How to call the command, without the prefix.
+    fn usage(&self) -> &'static str {
+        self.name()
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String;
+    /// Sample invocations, without the prefix.
+    fn examples(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Everyone
+    }
+
+    fn cooldown(&self) -> Cooldown {
+        Cooldown::default()
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response;
+}
+
+/// Logs a database failure hit while `sender` ran `command` and returns the
+/// reply to send instead.
+pub fn store_failure(command: &str, sender: &str, access: Access, error: StoreError) -> String {
+    log::error!("{command} failed for @{sender}: {error}");
+    format!("@{sender} {}", error.reply(access))
+}
+
+/// Runs `f` on the store for a command that works on the current channel, or
+/// the global scope when its arguments start with `--global`. Only senders
+/// who may change global commands can write to the global scope. `f` gets the
+/// remaining arguments, the scope and the sender, and a store failure becomes
+/// the reply. Writes also refresh the generated command lists they affect.
+pub async fn run_scoped<F>(
+    ctx: &CommandContext,
+    command: &'static str,
+    access: Access,
+    f: F,
+) -> Response
+where
+    F: FnOnce(&dyn Storage, &str, Option<&str>, &str) -> Result<String, StoreError>
+        + Send
+        + 'static,
+{
+    let sender = ctx.sender().to_owned();
+    let (global, args) = take_flag(&ctx.args, "--global");
+
+    if global && access == Access::Write && !ctx.can_change_global() {
+        log::warn!(
+            "{sender} tried to run {command} --global in #{} without global trust",
+            ctx.channel()
+        );
+        return Response::Message(format!(
+            "@{sender} only the broadcaster or users trusted in every channel can use --global."
+        ));
+    }
+
+    let scope = (!global).then(|| ctx.channel().to_owned());
+    let args = args.to_owned();
+    let config = ctx.services.config.clone();
+
+    let reply = ctx
+        .store()
+        .run(move |store| {
+            let reply = f(store, &args, scope.as_deref(), &sender)
+                .unwrap_or_else(|e| store_failure(command, &sender, access, e));
+            if access == Access::Write {
+                listing::refresh(store, &config, scope.as_deref());
+            }
+            reply
+        })
+        .await;
+
+    Response::Message(reply)
 }
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// How a custom command with several responses picks the one to send.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseMode {
+    #[default]
+    Random,
+    Rotate,
+    Weighted,
+}
+
+/// One of the responses of a custom command, with its weight for
+/// `ResponseMode::Weighted`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedResponse {
+    pub text: String,
+    pub weight: u32,
+}
+
+impl ResponseMode {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ResponseMode::Random => "random",
+            ResponseMode::Rotate => "rotate",
+            ResponseMode::Weighted => "weighted",
+        }
+    }
+
+    /// Picks a response. `turn` counts previous uses and is only looked at
+    /// when rotating; `rng` makes the random and weighted picks.
+    pub fn choose<'a>(
+        &self,
+        responses: &'a [WeightedResponse],
+        turn: u64,
+        rng: &mut impl Rng,
+    ) -> Option<&'a str> {
+        let response = match self {
+            ResponseMode::Random => responses.choose(rng)?,
+            ResponseMode::Rotate => {
+                responses.get((turn % responses.len().max(1) as u64) as usize)?
+            }
+            ResponseMode::Weighted => {
+                let weights = responses.iter().map(|response| response.weight);
+                match WeightedIndex::new(weights) {
+                    Ok(index) => &responses[index.sample(rng)],
+                    Err(_) => responses.first()?,
+                }
+            }
+        };
+
+        Some(&response.text)
+    }
+}
+
+impl FromStr for ResponseMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<ResponseMode, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "random" => Ok(ResponseMode::Random),
+            "rotate" | "roundrobin" => Ok(ResponseMode::Rotate),
+            "weighted" => Ok(ResponseMode::Weighted),
+            _ => Err(format!(
+                "Unknown mode `{value}`. Use random, rotate or weighted."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResponseMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
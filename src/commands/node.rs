@@ -1,4 +1,4 @@
-use super::Command;
+use super::{Command, CommandContext, Response, Role};
 use crate::helpers::has_at_least_n_args;
 use crate::register_command;
 
@@ -19,16 +19,37 @@ impl Command for Node {
         "node"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Runs JavaScript in a sandbox and sends its output."
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match run_node(args, sender).await {
-            Ok(output) => output,
+    fn usage(&self) -> &'static str {
+        "node <code>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["node console.log(1 + 1)"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        match run_node(&ctx.args, ctx.sender()).await {
+            // Chat has no line breaks, so the output goes out as one message
+            // that `command.max_parts` caps like any other.
+            Ok(output) => Response::from(
+                output
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
             Err(e) => {
                 log::error!("Execution error: {e}");
-                e.to_string()
+                Response::Message(e.to_string())
             }
         }
     }
@@ -36,7 +57,7 @@ impl Command for Node {
 
 async fn run_node(args: &str, sender: &str) -> Result<String> {
     if !has_at_least_n_args(args, 1) {
-        anyhow::bail!("USAGE: node <code>");
+        anyhow::bail!("USAGE: {}", Node.usage());
     }
 
     check_robocop_installed("vendor/robocop/bin")?;
@@ -1,4 +1,4 @@
-use super::Command;
+use super::{Command, CommandContext, Response};
 use crate::helpers::has_at_least_n_args;
 use crate::register_command;
 
@@ -14,11 +14,25 @@ impl Command for Odds {
         "odds"
     }
 
-    async fn execute(&self, args: &str, _sender: &str) -> String {
-        match parse_odds_args(args) {
-            Ok(params) => calculate_odds(params, args),
+    fn description(&self) -> &'static str {
+        "Calculates the odds of drawing enough successes from a deck."
+    }
+
+    fn usage(&self) -> &'static str {
+        "odds <deck size> <successes in deck> <draws> <successes needed>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["odds 60 24 7 3"]
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let reply = match parse_odds_args(&ctx.args) {
+            Ok(params) => calculate_odds(params, &ctx.args),
             Err(msg) => msg,
-        }
+        };
+
+        Response::Message(reply)
     }
 }
 
@@ -30,9 +44,7 @@ struct OddsParams {
 }
 
 fn parse_odds_args(args: &str) -> Result<OddsParams, String> {
-    let usage = String::from(
-        "USAGE: odds <Deck Size> <Number of Successes> <Number of Draws> <Successes Needed>",
-    );
+    let usage = format!("USAGE: {}", Odds.usage());
 
     if !has_at_least_n_args(args, 4) {
         return Err(usage);
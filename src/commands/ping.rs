@@ -1,4 +1,4 @@
-use super::Command;
+use super::{Command, CommandContext, Response};
 use crate::register_command;
 
 use async_trait::async_trait;
@@ -12,8 +12,12 @@ impl Command for Ping {
         "ping"
     }
 
-    async fn execute(&self, _args: &str, _sender: &str) -> String {
-        String::from("Pong!")
+    fn description(&self) -> &'static str {
+        "Checks that the bot is alive."
+    }
+
+    async fn execute(&self, _ctx: &CommandContext) -> Response {
+        Response::Message(String::from("Pong!"))
     }
 }
 
@@ -17,9 +17,13 @@ impl Registry {
         cmds.clone()
     }
 
+    /// Finds the builtin that answers to `name`, either as its name or as
+    /// one of its aliases.
     pub fn get(name: &str) -> Option<Arc<dyn Command + Send + Sync>> {
         let cmds = COMMANDS.read().unwrap();
-        cmds.iter().find(|c| c.name() == name).cloned()
+        cmds.iter()
+            .find(|c| c.name() == name || c.aliases().contains(&name))
+            .cloned()
     }
 }
 
@@ -0,0 +1,62 @@
+use std::fmt;
+
+/// What the bot should send back after handling a chat message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// Nothing is sent.
+    None,
+    /// A plain message in the channel the trigger came from.
+    Message(String),
+    /// A message threaded as a reply to the triggering message.
+    Reply(String),
+    /// A private message to the user who triggered the response.
+    Whisper(String),
+    /// Several responses, sent in order.
+    Sequence(Vec<Response>),
+}
+
+impl Response {
+    /// Flattens nested sequences and drops empty responses, leaving only
+    /// responses that each send exactly one message.
+    pub fn into_parts(self) -> Vec<Response> {
+        match self {
+            Response::None => vec![],
+            Response::Message(text) | Response::Reply(text) | Response::Whisper(text)
+                if text.trim().is_empty() =>
+            {
+                vec![]
+            }
+            Response::Sequence(responses) => responses
+                .into_iter()
+                .flat_map(Response::into_parts)
+                .collect(),
+            response => vec![response],
+        }
+    }
+}
+
+/// Plain text becomes a channel message, or no response at all when empty.
+impl From<String> for Response {
+    fn from(text: String) -> Response {
+        if text.trim().is_empty() {
+            Response::None
+        } else {
+            Response::Message(text)
+        }
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Response::None => write!(f, "<none>"),
+            Response::Message(text) => write!(f, "{text}"),
+            Response::Reply(text) => write!(f, "reply: {text}"),
+            Response::Whisper(text) => write!(f, "whisper: {text}"),
+            Response::Sequence(responses) => {
+                let parts: Vec<String> = responses.iter().map(|r| r.to_string()).collect();
+                write!(f, "[{}]", parts.join(" | "))
+            }
+        }
+    }
+}
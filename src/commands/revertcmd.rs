@@ -0,0 +1,125 @@
+use super::{run_scoped, Command, CommandContext, Response, Role};
+use crate::commands::registry::is_builtin;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::CommandStore;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
+use crate::register_command;
+
+use async_trait::async_trait;
+
+#[derive(Default)]
+pub struct RevertCmd;
+
+#[async_trait]
+impl Command for RevertCmd {
+    fn name(&self) -> &'static str {
+        "revertcmd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Restores a custom command to an earlier revision, or undoes its latest change."
+    }
+
+    fn usage(&self) -> &'static str {
+        "revertcmd [--global] <name> [revision]"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["revertcmd rules", "revertcmd rules 2"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "revertcmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok((name, revision)) => handle_command(store, name, revision, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
+    }
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<(&'a str, Option<u32>), String> {
+    let usage = format!("@{sender} USAGE: {}", RevertCmd.usage());
+
+    if !has_at_least_n_args(args, 1) {
+        return Err(usage);
+    }
+
+    let mut parts = args.split_whitespace();
+    let name = parts.next().unwrap();
+
+    if !is_valid_command_name(name) {
+        log::error!("{sender} tried to revert an invalid command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    let revision = match parts.next() {
+        Some(revision) => Some(
+            revision
+                .trim_start_matches('#')
+                .parse()
+                .map_err(|_| usage)?,
+        ),
+        None => None,
+    };
+
+    Ok((name, revision))
+}
+
+/// Restores the response `name` had right after `revision`, or undoes the
+/// latest change when no revision is given.
+fn handle_command(
+    store: &dyn CommandStore,
+    name: &str,
+    revision: Option<u32>,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
+    if is_builtin(name) {
+        return Ok(String::from("Built-in commands have no history."));
+    }
+
+    let number = match revision {
+        Some(number) => number,
+        // Deletions leave nothing to restore, so undoing the latest change
+        // goes back to the newest earlier revision that set a response.
+        None => match store
+            .get_revisions(name, scope, u32::MAX as usize)?
+            .iter()
+            .skip(1)
+            .find(|revision| revision.new_response.is_some())
+        {
+            Some(previous) => previous.number,
+            None => return Ok(format!("{name} has no earlier revision to revert to.")),
+        },
+    };
+
+    let Some(revision) = store.get_revision(name, scope, number)? else {
+        return Ok(format!("{name} has no revision #{number}."));
+    };
+
+    let Some(response) = revision.new_response else {
+        return Ok(format!(
+            "Revision #{number} deleted {name}, so there is nothing to restore."
+        ));
+    };
+
+    if !store.revert_command(name, scope, &response, sender)? {
+        return Ok(format!(
+            "Command {name} does not exist. Use undelcmd to restore it first."
+        ));
+    }
+
+    log::info!("{sender} reverted {name} to revision #{number}");
+    Ok(format!("{name} was reverted to revision #{number}."))
+}
+
+register_command!(RevertCmd);
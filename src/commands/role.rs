@@ -0,0 +1,99 @@
+use std::fmt;
+use std::str::FromStr;
+
+use twitch_irc::message::PrivmsgMessage;
+
+use super::context::Services;
+
+/// Permission tiers, from least to most privileged. A user holds the highest
+/// role their badges or the trusted list give them, and may run any command
+/// whose minimum role is at or below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    #[default]
+    Everyone,
+    Subscriber,
+    Vip,
+    Moderator,
+    Trusted,
+    Broadcaster,
+}
+
+impl Role {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Role::Everyone => "everyone",
+            Role::Subscriber => "subscriber",
+            Role::Vip => "vip",
+            Role::Moderator => "moderator",
+            Role::Trusted => "trusted",
+            Role::Broadcaster => "broadcaster",
+        }
+    }
+
+    pub async fn of(message: &PrivmsgMessage, services: &Services) -> Role {
+        let sender = &message.sender.login;
+        let channel = &message.channel_login;
+
+        let from_badges = message
+            .badges
+            .iter()
+            .map(|badge| match badge.name.as_str() {
+                "broadcaster" => Role::Broadcaster,
+                "moderator" => Role::Moderator,
+                "vip" => Role::Vip,
+                "subscriber" | "founder" => Role::Subscriber,
+                _ => Role::Everyone,
+            })
+            .max()
+            .unwrap_or_default();
+
+        let trusted = services
+            .config
+            .trusted_users_for(channel)
+            .iter()
+            .any(|user| user == sender)
+            || {
+                let (sender, channel) = (sender.clone(), channel.clone());
+                services
+                    .store
+                    .run(move |store| {
+                        store.is_trusted(&sender, &channel).unwrap_or_else(|e| {
+                            log::error!("Could not check whether {sender} is trusted: {e}");
+                            false
+                        })
+                    })
+                    .await
+            };
+
+        match (sender == channel, trusted) {
+            (true, _) => Role::Broadcaster,
+            (false, true) => from_badges.max(Role::Trusted),
+            (false, false) => from_badges,
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Role, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "everyone" | "all" => Ok(Role::Everyone),
+            "subscriber" | "sub" => Ok(Role::Subscriber),
+            "vip" => Ok(Role::Vip),
+            "moderator" | "mod" => Ok(Role::Moderator),
+            "trusted" => Ok(Role::Trusted),
+            "broadcaster" => Ok(Role::Broadcaster),
+            _ => Err(format!(
+                "Unknown role `{value}`. Use everyone, subscriber, vip, moderator, trusted or broadcaster."
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
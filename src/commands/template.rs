@@ -0,0 +1,121 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use regex::{Captures, Regex};
+
+static PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{([a-z]+[0-9]*)(?::([^{}]*))?\}").unwrap());
+
+static MACRO: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{cmd:((?:[^{}]|\{[^{}]*\})*)\}").unwrap());
+
+/// Values a custom command response can refer to.
+pub struct Variables<'a> {
+    pub sender: &'a str,
+    pub channel: &'a str,
+    pub args: &'a str,
+    pub count: u64,
+}
+
+/// Fills in the placeholders of a custom command response:
+///
+/// - `{sender}`, `{channel}` and `{count}`
+/// - `{args}`, every argument, and `{arg1}`, `{arg2:default}`, ... one at a time
+/// - `{touser}`, the first argument without its `@`, or the sender
+/// - `{random:a|b|c}`, one of the options
+/// - `{time:America/Sao_Paulo}`, the current time in a timezone, UTC by default
+///
+/// Anything else in braces is left as it is. `rng` picks the `{random}` options.
+pub fn render(template: &str, vars: &Variables, rng: &mut impl Rng) -> String {
+    let args: Vec<&str> = vars.args.split_whitespace().collect();
+
+    PLACEHOLDER
+        .replace_all(template, |caps: &Captures| {
+            let name = &caps[1];
+            let param = caps.get(2).map(|param| param.as_str());
+
+            expand(name, param, vars, &args, rng).unwrap_or_else(|| String::from(&caps[0]))
+        })
+        .into_owned()
+}
+
+fn expand(
+    name: &str,
+    param: Option<&str>,
+    vars: &Variables,
+    args: &[&str],
+    rng: &mut impl Rng,
+) -> Option<String> {
+    let value = match (name, param) {
+        ("sender", None) => String::from(vars.sender),
+        ("channel", None) => String::from(vars.channel),
+        ("count", None) => vars.count.to_string(),
+        ("args", None) => args.join(" "),
+        ("touser", None) => {
+            let user = args
+                .first()
+                .map_or(vars.sender, |arg| arg.trim_start_matches('@'));
+            String::from(user)
+        }
+        ("random", Some(options)) => {
+            let options: Vec<&str> = options.split('|').collect();
+            String::from(*options.choose(rng)?)
+        }
+        ("time", tz) => {
+            let tz: Tz = match tz.unwrap_or("UTC").parse() {
+                Ok(tz) => tz,
+                Err(e) => {
+                    log::warn!("Invalid timezone in custom command: {e}");
+                    return None;
+                }
+            };
+            Utc::now().with_timezone(&tz).format("%H:%M").to_string()
+        }
+        (arg, default) => {
+            let index: usize = arg.strip_prefix("arg")?.parse().ok()?;
+            let value = args.get(index.checked_sub(1)?).copied();
+            String::from(value.or(default).unwrap_or_default())
+        }
+    };
+
+    Some(value)
+}
+
+/// A piece of a custom command response: either text to send, or another
+/// command to run, such as `wttr São Paulo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Command(String),
+}
+
+/// Splits `template` around `{cmd:name args}` macros, rendering the
+/// placeholders in both the text and the macro arguments, for example
+/// `{cmd:wttr {args}}`. Placeholders are filled in only once, so arguments
+/// can never smuggle in a macro of their own.
+pub fn segments(template: &str, vars: &Variables, rng: &mut impl Rng) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut last = 0;
+
+    for caps in MACRO.captures_iter(template) {
+        let whole = caps.get(0).unwrap();
+        if whole.start() > last {
+            segments.push(Segment::Text(render(
+                &template[last..whole.start()],
+                vars,
+                rng,
+            )));
+        }
+        segments.push(Segment::Command(
+            render(&caps[1], vars, rng).trim().to_string(),
+        ));
+        last = whole.end();
+    }
+
+    if last < template.len() {
+        segments.push(Segment::Text(render(&template[last..], vars, rng)));
+    }
+
+    segments
+}
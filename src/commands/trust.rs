@@ -1,10 +1,9 @@
-use super::Command;
-use crate::database::sqlite;
-use crate::helpers::has_at_least_n_args;
+use super::{store_failure, Command, CommandContext, Response, Role};
+use crate::database::error::{Access, StoreError};
+use crate::database::store::TrustStore;
+use crate::helpers::{has_at_least_n_args, is_valid_username};
 use crate::register_command;
 
-use regex::Regex;
-
 use async_trait::async_trait;
 
 #[derive(Default)]
@@ -16,26 +15,48 @@ impl Command for Trust {
         "trust"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Lets a user manage commands in this channel."
+    }
+
+    fn usage(&self) -> &'static str {
+        "trust <username>"
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match parse_args(args, sender) {
-            Ok(name) => handle_command(name, sender),
-            Err(msg) => msg,
-        }
+    fn examples(&self) -> &'static [&'static str] {
+        &["trust helper"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let sender = ctx.sender().to_owned();
+        let channel = ctx.channel().to_owned();
+        let args = ctx.args.clone();
+
+        let reply = ctx
+            .store()
+            .run(move |store| match parse_args(&args, &sender) {
+                Ok(name) => handle_command(store, name, &channel, &sender)
+                    .unwrap_or_else(|e| store_failure("trust", &sender, Access::Write, e)),
+                Err(msg) => msg,
+            })
+            .await;
+
+        Response::Message(reply)
     }
 }
 
 fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     if !has_at_least_n_args(args, 1) {
-        return Err(format!("@{sender} USAGE: trust <username>"));
+        return Err(format!("@{sender} USAGE: {}", Trust.usage()));
     }
 
     let username: &str = args.split(' ').collect::<Vec<&str>>()[0];
 
-    if !is_valid_name(username) {
+    if !is_valid_username(username) {
         log::error!("{sender} tried to trust an invalid username: {username}");
         return Err("Username must be a valid Twitch username.".to_string());
     }
@@ -43,15 +64,16 @@ fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     Ok(username)
 }
 
-fn is_valid_name(name: &str) -> bool {
-    Regex::new("^[a-z][a-z0-9_]{2,24}$").unwrap().is_match(name)
-}
-
-fn handle_command(username: &str, sender: &str) -> String {
-    sqlite::trust_user(username);
-    log::info!("{sender} trusted user: {username}");
+fn handle_command(
+    store: &dyn TrustStore,
+    username: &str,
+    channel: &str,
+    sender: &str,
+) -> Result<String, StoreError> {
+    store.trust_user(username, channel)?;
+    log::info!("{sender} trusted user {username} in #{channel}");
 
-    format!("{username} has been trusted.")
+    Ok(format!("{username} has been trusted."))
 }
 
 register_command!(Trust);
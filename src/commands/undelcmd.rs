@@ -0,0 +1,88 @@
+use super::{run_scoped, Command, CommandContext, Response, Role};
+use crate::commands::registry::is_builtin;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::CommandStore;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
+use crate::register_command;
+
+use async_trait::async_trait;
+
+#[derive(Default)]
+pub struct UndelCmd;
+
+#[async_trait]
+impl Command for UndelCmd {
+    fn name(&self) -> &'static str {
+        "undelcmd"
+    }
+
+    fn description(&self) -> &'static str {
+        "Restores a deleted custom command."
+    }
+
+    fn usage(&self) -> &'static str {
+        "undelcmd [--global] <name>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["undelcmd hug"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "undelcmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok(name) => handle_command(store, name, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
+    }
+}
+
+fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
+    if !has_at_least_n_args(args, 1) {
+        return Err(format!("@{sender} USAGE: {}", UndelCmd.usage()));
+    }
+
+    let name: &str = args.split(' ').collect::<Vec<&str>>()[0];
+
+    if !is_valid_command_name(name) {
+        log::error!("{sender} tried to restore an invalid command name: {name}");
+        return Err("Command name can only contain letters and numbers.".to_string());
+    }
+
+    Ok(name)
+}
+
+fn handle_command(
+    store: &dyn CommandStore,
+    name: &str,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
+    if is_builtin(name) {
+        return Ok(String::from(
+            "Built-in commands cannot be deleted or restored.",
+        ));
+    }
+
+    if store.command_exists(name, scope)? {
+        return Ok(format!("Command {name} is not deleted."));
+    }
+
+    if !store.restore_command(name, scope, sender)? {
+        return Ok(format!("There is no deleted command called {name}."));
+    }
+
+    log::info!("{sender} restored the command {name}");
+    Ok(String::from("Command restored!"))
+}
+
+register_command!(UndelCmd);
@@ -1,10 +1,9 @@
-use super::Command;
-use crate::database::sqlite;
-use crate::helpers::has_at_least_n_args;
+use super::{store_failure, Command, CommandContext, Response, Role};
+use crate::database::error::{Access, StoreError};
+use crate::database::store::TrustStore;
+use crate::helpers::{has_at_least_n_args, is_valid_username};
 use crate::register_command;
 
-use regex::Regex;
-
 use async_trait::async_trait;
 
 #[derive(Default)]
@@ -16,26 +15,49 @@ impl Command for Untrust {
         "untrust"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Revokes trust from a user in this channel."
+    }
+
+    fn usage(&self) -> &'static str {
+        "untrust <username>"
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match parse_args(args, sender) {
-            Ok(name) => handle_command(name, sender),
-            Err(msg) => msg,
-        }
+    fn examples(&self) -> &'static [&'static str] {
+        &["untrust helper"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let sender = ctx.sender().to_owned();
+        let channel = ctx.channel().to_owned();
+        let args = ctx.args.clone();
+        let config_trusted = ctx.config().trusted_users_for(&channel).to_vec();
+
+        let reply = ctx
+            .store()
+            .run(move |store| match parse_args(&args, &sender) {
+                Ok(name) => handle_command(store, name, &channel, &config_trusted, &sender)
+                    .unwrap_or_else(|e| store_failure("untrust", &sender, Access::Write, e)),
+                Err(msg) => msg,
+            })
+            .await;
+
+        Response::Message(reply)
     }
 }
 
 fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     if !has_at_least_n_args(args, 1) {
-        return Err(format!("@{sender} USAGE: untrust <username>"));
+        return Err(format!("@{sender} USAGE: {}", Untrust.usage()));
     }
 
     let username: &str = args.split(' ').collect::<Vec<&str>>()[0];
 
-    if !is_valid_name(username) {
+    if !is_valid_username(username) {
         log::error!("{sender} tried to untrust an invalid username: {username}");
         return Err("Username must be a valid Twitch username.".to_string());
     }
@@ -43,15 +65,30 @@ fn parse_args<'a>(args: &'a str, sender: &str) -> Result<&'a str, String> {
     Ok(username)
 }
 
-fn is_valid_name(name: &str) -> bool {
-    Regex::new("^[a-z][a-z0-9_]{2,24}$").unwrap().is_match(name)
-}
+/// Removes trust granted with `trust`. Users trusted by the config keep it,
+/// so the reply says so instead of claiming they lost it.
+fn handle_command(
+    store: &dyn TrustStore,
+    username: &str,
+    channel: &str,
+    config_trusted: &[String],
+    sender: &str,
+) -> Result<String, StoreError> {
+    let untrusted = store.untrust_user(username, channel)?;
 
-fn handle_command(username: &str, sender: &str) -> String {
-    sqlite::untrust_user(username);
-    log::info!("{sender} untrusted user: {username}");
+    if config_trusted.iter().any(|user| user == username) {
+        log::warn!("{sender} tried to untrust {username} in #{channel}, who is trusted by config");
+        return Ok(format!(
+            "{username} is trusted by config and can't be untrusted here."
+        ));
+    }
+
+    if !untrusted {
+        return Ok(format!("{username} was not trusted."));
+    }
 
-    format!("{username} has been untrusted.")
+    log::info!("{sender} untrusted user {username} in #{channel}");
+    Ok(format!("{username} has been untrusted."))
 }
 
 register_command!(Untrust);
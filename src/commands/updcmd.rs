@@ -1,11 +1,10 @@
-use super::Command;
+use super::{run_scoped, Command, CommandContext, Response, Role};
 use crate::commands::registry::is_builtin;
-use crate::database::sqlite;
-use crate::helpers::has_at_least_n_args;
+use crate::database::error::{Access, StoreError};
+use crate::database::store::CommandStore;
+use crate::helpers::{has_at_least_n_args, is_valid_command_name};
 use crate::register_command;
 
-use regex::Regex;
-
 use async_trait::async_trait;
 
 #[derive(Default)]
@@ -17,28 +16,46 @@ impl Command for UpdateCmd {
         "updcmd"
     }
 
-    fn requires_trust(&self) -> bool {
-        true
+    fn description(&self) -> &'static str {
+        "Changes the response of an existing custom command."
+    }
+
+    fn usage(&self) -> &'static str {
+        "updcmd [--global] <name> <response>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["updcmd hug {sender} squeezes {touser}"]
+    }
+
+    fn required_role(&self) -> Role {
+        Role::Trusted
     }
 
-    async fn execute(&self, args: &str, sender: &str) -> String {
-        match parse_args(args, sender) {
-            Ok((name, response)) => handle_command(name, response, sender),
-            Err(msg) => msg,
-        }
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        run_scoped(
+            ctx,
+            "updcmd",
+            Access::Write,
+            |store, args, scope, sender| match parse_args(args, sender) {
+                Ok((name, response)) => handle_command(store, name, response, scope, sender),
+                Err(msg) => Ok(msg),
+            },
+        )
+        .await
     }
 }
 
 fn parse_args<'a>(args: &'a str, sender: &str) -> Result<(&'a str, &'a str), String> {
     if !has_at_least_n_args(args, 2) {
-        return Err(format!("@{sender} USAGE: updcmd <name> <response>"));
+        return Err(format!("@{sender} USAGE: {}", UpdateCmd.usage()));
     }
 
     let mut parts = args.splitn(2, ' ');
     let name = parts.next().unwrap_or("");
     let response = parts.next().unwrap_or("").trim();
 
-    if !is_valid_name(name) {
+    if !is_valid_command_name(name) {
         log::error!("{sender} tried to update an invalid command name: {name}");
         return Err("Command name can only contain letters and numbers.".to_string());
     }
@@ -46,23 +63,30 @@ fn parse_args<'a>(args: &'a str, sender: &str) -> Result<(&'a str, &'a str), Str
     Ok((name, response))
 }
 
-fn is_valid_name(name: &str) -> bool {
-    Regex::new(r"^[a-zA-Z0-9]+$").unwrap().is_match(name)
-}
-
-fn handle_command(name: &str, response: &str, sender: &str) -> String {
+fn handle_command(
+    store: &dyn CommandStore,
+    name: &str,
+    response: &str,
+    scope: Option<&str>,
+    sender: &str,
+) -> Result<String, StoreError> {
     if is_builtin(name) {
         log::error!(
             "{sender} tried to update a command that already exists as a built-in command: {name}"
         );
 
-        return String::from(
+        return Ok(String::from(
             "The command you are trying to update already exists as a built-in command.",
-        );
+        ));
+    }
+
+    if !store.update_command_response(name, scope, response, sender)? {
+        return Ok(format!(
+            "Command {name} does not exist. Use addcmd to create it."
+        ));
     }
 
-    sqlite::update_command_response(name, response);
-    String::from("Command updated!")
+    Ok(String::from("Command updated!"))
 }
 
 register_command!(UpdateCmd);
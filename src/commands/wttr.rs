@@ -1,4 +1,4 @@
-use super::Command;
+use super::{Command, CommandContext, Cooldown, Response};
 use crate::register_command;
 
 use async_trait::async_trait;
@@ -12,14 +12,30 @@ impl Command for Wttr {
         "wttr"
     }
 
-    async fn execute(&self, args: &str, _sender: &str) -> String {
-        let url = build_weather_url(args);
+    fn description(&self) -> &'static str {
+        "Shows the current weather for a place."
+    }
+
+    fn usage(&self) -> &'static str {
+        "wttr <place>"
+    }
+
+    fn examples(&self) -> &'static [&'static str] {
+        &["wttr São Paulo"]
+    }
+
+    fn cooldown(&self) -> Cooldown {
+        Cooldown::from_secs(5, 30)
+    }
+
+    async fn execute(&self, ctx: &CommandContext) -> Response {
+        let url = build_weather_url(&ctx.args);
 
-        match fetch_weather(&url).await {
-            Ok(body) => body,
+        match fetch_weather(ctx.http(), &url).await {
+            Ok(body) => Response::from(body),
             Err(e) => {
                 log::error!("Failed to fetch weather: {e}");
-                String::from("Could not retrieve weather data.")
+                Response::Message(String::from("Could not retrieve weather data."))
             }
         }
     }
@@ -31,8 +47,8 @@ fn build_weather_url(args: &str) -> String {
     format!("https://wttr.in/{location}?format={format}&m")
 }
 
-async fn fetch_weather(url: &str) -> Result<String, reqwest::Error> {
-    let response = reqwest::get(url).await?;
+async fn fetch_weather(http: &reqwest::Client, url: &str) -> Result<String, reqwest::Error> {
+    let response = http.get(url).send().await?;
     response.text().await
 }
 
@@ -1,12 +1,33 @@
+use std::collections::HashMap;
 use std::fs;
 
+use crate::commands::Role;
+
+pub const DEFAULT_DATABASE_PATH: &str = "./database/rusted.db";
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub user: Option<String>,
     pub token: Option<String>,
-    pub channel: String,
+    pub channels: Vec<String>,
     pub prefix: String,
     pub trusted_users: Vec<String>,
+    pub cooldown_bypass: Role,
+    pub cooldown_message: Option<String>,
+    pub max_parts: usize,
+    pub suggestions: bool,
+    pub list_url: Option<String>,
+    pub list_path: Option<String>,
+    pub database_path: String,
+    pub overrides: HashMap<String, ChannelOverrides>,
+}
+
+/// Settings that a `[channel]` section can override for a single channel.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelOverrides {
+    pub prefix: Option<String>,
+    pub trusted_users: Option<Vec<String>>,
+    pub suggestions: Option<bool>,
 }
 
 impl Config {
@@ -17,27 +38,19 @@ impl Config {
         };
 
         let mut config = default();
+        let mut section: Option<String> = None;
 
         let loaded_config = file
             .split('\n')
             .map(|line| line.trim())
             .filter(|line| !line.is_empty())
-            .map(|line| {
-                if line.contains('#') {
-                    let mut new_line = line;
-                    new_line = &new_line[..new_line.find('#').unwrap()];
-                    new_line
-                } else {
-                    line
-                }
-            })
-            .map(|line| {
-                line.split('=')
-                    .map(|part| part.trim())
-                    .collect::<Vec<&str>>()
+            .map(strip_comment)
+            .map(|line| match line.split_once('=') {
+                Some((key, value)) => vec![key.trim(), value.trim()],
+                None => vec![line.trim()],
             })
             .map(|line| {
-                if line[1].starts_with('"') && line[1].ends_with('"') {
+                if line.len() == 2 && line[1].starts_with('"') && line[1].ends_with('"') {
                     let mut new_line = line.clone();
                     new_line[1] = &line[1][1..line[1].len() - 1];
                     new_line
@@ -48,6 +61,31 @@ impl Config {
             .collect::<Vec<Vec<&str>>>();
 
         for line in loaded_config {
+            if let [header] = line[..] {
+                if header.starts_with('[') && header.ends_with(']') {
+                    let channel = header[1..header.len() - 1].trim().to_ascii_lowercase();
+                    config.overrides.entry(channel.clone()).or_default();
+                    section = Some(channel);
+                }
+                continue;
+            }
+
+            if let Some(channel) = &section {
+                let overrides = config.overrides.entry(channel.clone()).or_default();
+                match line[..] {
+                    ["command.prefix", prefix] => overrides.prefix = Some(String::from(prefix)),
+                    ["command.trusted", trusted_users] => {
+                        overrides.trusted_users = Some(parse_list(trusted_users))
+                    }
+                    ["command.suggestions", enabled] => {
+                        overrides.suggestions = parse_bool("command.suggestions", enabled)
+                    }
+                    [unknown, _] => log::warn!("Unknown config option for [{channel}]: {unknown}"),
+                    _ => (),
+                }
+                continue;
+            }
+
             match line[..] {
                 ["twitch.user", user] => config.user = Some(String::from(user)),
                 ["twitch.token", token] => {
@@ -57,31 +95,143 @@ impl Config {
                     }
                     config.token = Some(token);
                 }
-                ["twitch.channel", channel] => config.channel = String::from(channel),
+                ["twitch.channel", channel] => config.channels = vec![channel.to_ascii_lowercase()],
+                ["twitch.channels", channels] => {
+                    config.channels = parse_list(&channels.to_ascii_lowercase());
+                }
                 ["command.prefix", prefix] => config.prefix = String::from(prefix),
+                ["database.path", path] => config.database_path = String::from(path),
                 ["command.trusted", trusted_users] => {
-                    let trusted_users: Vec<String> = trusted_users
-                        .split(',')
-                        .map(|user| user.trim())
-                        .map(String::from)
-                        .collect();
-                    config.trusted_users = trusted_users;
+                    config.trusted_users = parse_list(trusted_users);
+                }
+                ["command.max_parts", max_parts] => match max_parts.parse() {
+                    Ok(max_parts) => config.max_parts = max_parts,
+                    Err(e) => log::warn!("Invalid command.max_parts: {e}"),
+                },
+                ["command.suggestions", enabled] => {
+                    if let Some(enabled) = parse_bool("command.suggestions", enabled) {
+                        config.suggestions = enabled;
+                    }
+                }
+                ["command.list_url", url] => config.list_url = Some(String::from(url)),
+                ["command.list_path", path] => config.list_path = Some(String::from(path)),
+                ["command.cooldown_bypass", role] => match role.parse() {
+                    Ok(role) => config.cooldown_bypass = role,
+                    Err(e) => log::warn!("Invalid command.cooldown_bypass: {e}"),
+                },
+                ["command.cooldown_message", message] => {
+                    config.cooldown_message = Some(String::from(message));
                 }
                 [unknown, _] => log::warn!("Unknown config option: {unknown}"),
                 _ => (),
             }
         }
 
+        if config.list_url.is_some() && config.list_path.is_none() {
+            log::warn!(
+                "command.list_url is ignored without command.list_path to write the list to"
+            );
+        }
+
+        for channel in config.overrides.keys() {
+            if !config.channels.contains(channel) {
+                log::warn!("Config section [{channel}] does not match any joined channel");
+            }
+        }
+
         config
     }
+
+    pub fn prefix_for(&self, channel: &str) -> &str {
+        self.overrides
+            .get(channel)
+            .and_then(|overrides| overrides.prefix.as_deref())
+            .unwrap_or(&self.prefix)
+    }
+
+    pub fn suggestions_for(&self, channel: &str) -> bool {
+        self.overrides
+            .get(channel)
+            .and_then(|overrides| overrides.suggestions)
+            .unwrap_or(self.suggestions)
+    }
+
+    /// Returns the address of the generated command list for `channel`, if
+    /// one is configured. The list is only generated when `list_path` is set
+    /// too, so a lone `list_url` is ignored.
+    pub fn list_url_for(&self, channel: &str) -> Option<String> {
+        self.list_path.as_ref()?;
+        self.list_url
+            .as_ref()
+            .map(|url| url.replace("{channel}", channel))
+    }
+
+    /// Returns the file the command list for `channel` is written to, if one
+    /// is configured.
+    pub fn list_path_for(&self, channel: &str) -> Option<String> {
+        self.list_path
+            .as_ref()
+            .map(|path| path.replace("{channel}", channel))
+    }
+
+    pub fn trusted_users_for(&self, channel: &str) -> &[String] {
+        self.overrides
+            .get(channel)
+            .and_then(|overrides| overrides.trusted_users.as_deref())
+            .unwrap_or(&self.trusted_users)
+    }
+}
+
+/// Cuts a `#` comment off `line`. A `#` only starts a comment at the start of
+/// the line or after whitespace, so values such as `https://host/#list` keep
+/// theirs.
+fn strip_comment(line: &str) -> &str {
+    let mut previous = None;
+
+    for (index, c) in line.char_indices() {
+        if c == '#' && previous.is_none_or(char::is_whitespace) {
+            return &line[..index];
+        }
+        previous = Some(c);
+    }
+
+    line
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_bool(key: &str, value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => {
+            log::warn!("Invalid {key}: expected true or false, got {value}");
+            None
+        }
+    }
 }
 
 pub fn default() -> Config {
     Config {
         user: None,
         token: None,
-        channel: String::from("commanderroot"),
+        channels: vec![String::from("commanderroot")],
         prefix: String::from("!"),
         trusted_users: vec![],
+        cooldown_bypass: Role::Moderator,
+        cooldown_message: None,
+        max_parts: 3,
+        suggestions: false,
+        list_url: None,
+        list_path: None,
+        database_path: String::from(DEFAULT_DATABASE_PATH),
+        overrides: HashMap::new(),
     }
 }
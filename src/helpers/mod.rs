@@ -1,3 +1,114 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static COMMAND_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9]+$").unwrap());
+
+static USERNAME: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z][a-z0-9_]{2,24}$").unwrap());
+
 pub fn has_at_least_n_args(args: &str, n: usize) -> bool {
     args.split_whitespace().take(n).count() >= n
 }
+
+/// Strips a leading `flag` (such as `--global`) from `args`, returning whether
+/// it was present and the remaining arguments.
+pub fn take_flag<'a>(args: &'a str, flag: &str) -> (bool, &'a str) {
+    let trimmed = args.trim_start();
+    match trimmed.split_once(' ') {
+        Some((first, rest)) if first == flag => (true, rest.trim_start()),
+        None if trimmed == flag => (true, ""),
+        _ => (false, args),
+    }
+}
+
+/// Whether `name` can name a custom command or alias: letters and numbers
+/// only.
+pub fn is_valid_command_name(name: &str) -> bool {
+    COMMAND_NAME.is_match(name)
+}
+
+/// Whether `name` looks like a Twitch login name.
+pub fn is_valid_username(name: &str) -> bool {
+    USERNAME.is_match(name)
+}
+
+/// Number of single-character insertions, deletions and substitutions needed
+/// to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+pub const MAX_TWITCH_CHAT_MESSAGE_LENGTH: usize = 500;
+
+/// Fits `text` into chat messages of at most `MAX_TWITCH_CHAT_MESSAGE_LENGTH`
+/// characters. Long text is split on word boundaries into parts numbered like
+/// `(1/3)`; whatever does not fit in `max_parts` is cut off with an ellipsis.
+pub fn split_message(text: &str, max_parts: usize) -> Vec<String> {
+    if text.chars().count() <= MAX_TWITCH_CHAT_MESSAGE_LENGTH {
+        return vec![String::from(text)];
+    }
+
+    if max_parts <= 1 {
+        return vec![truncate(text, MAX_TWITCH_CHAT_MESSAGE_LENGTH)];
+    }
+
+    let width = MAX_TWITCH_CHAT_MESSAGE_LENGTH - format!(" ({max_parts}/{max_parts})").len();
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        if parts.len() == max_parts - 1 {
+            parts.push(truncate(rest, width));
+            break;
+        }
+
+        let (part, tail) = split_at_word(rest, width);
+        parts.push(String::from(part));
+        rest = tail.trim_start();
+    }
+
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, part)| format!("{part} ({}/{total})", i + 1))
+        .collect()
+}
+
+/// Cuts `text` down to `width` characters, ending on a word boundary with an
+/// ellipsis when anything was removed.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return String::from(text);
+    }
+
+    let (head, _) = split_at_word(text, width - 1);
+    format!("{head}…")
+}
+
+/// Splits `text` after at most `width` characters, preferring the last
+/// whitespace and only cutting a word when it is longer than `width`.
+fn split_at_word(text: &str, width: usize) -> (&str, &str) {
+    let Some((end, next)) = text.char_indices().nth(width) else {
+        return (text, "");
+    };
+
+    let head = &text[..end];
+    match head.rfind(char::is_whitespace) {
+        Some(space) if !next.is_whitespace() && !head[..space].trim_end().is_empty() => {
+            (head[..space].trim_end(), &text[space..])
+        }
+        _ => (head.trim_end(), &text[end..]),
+    }
+}
@@ -4,38 +4,190 @@ mod helpers;
 mod messages;
 mod services;
 
+#[cfg(test)]
+mod tests;
+
+use commands::context::Services;
 use config::Config;
 use fern::colors::{Color, ColoredLevelConfig};
+use services::database::memory::MemoryStore;
+use services::database::migrations;
+use services::database::sqlite::{SqliteStore, IN_MEMORY};
+use services::database::store::Store;
+use services::transport::Backend;
 use services::{database, twitch};
 use std::process::ExitCode;
 
+const USAGE: &str = "Usage: rustedbot [--console] [--database <path>] <config_path>
+       rustedbot db migrate [--dry-run] [--database <path>] <config_path>";
+
+/// Flags and positional arguments shared by every way of running the bot.
+#[derive(Default)]
+struct Options {
+    console: bool,
+    dry_run: bool,
+    database: Option<String>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Option<Options> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--console" => options.console = true,
+                "--dry-run" => options.dry_run = true,
+                "--database" => options.database = Some(args.next()?.clone()),
+                flag if flag.starts_with("--") => {
+                    println!("Unknown option: {flag}");
+                    return None;
+                }
+                _ => options.positional.push(arg.clone()),
+            }
+        }
+
+        Some(options)
+    }
+
+    /// The database named on the command line wins over the config file.
+    fn database_path<'a>(&'a self, config: &'a Config) -> &'a str {
+        self.database.as_deref().unwrap_or(&config.database_path)
+    }
+}
+
 fn main() -> ExitCode {
-    init_logger().expect("Failed to initialize logger.");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let Some(options) = Options::parse(&args) else {
+        println!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    match &options.positional[..] {
+        [db, subcommand, config_path] if db == "db" => {
+            run_db_command(&options, subcommand, config_path)
+        }
+        [config_path] => run_bot(&options, config_path),
+        _ => {
+            println!("{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_bot(options: &Options, config_path: &str) -> ExitCode {
+    init_logger(options.console).expect("Failed to initialize logger.");
     log::info!("Starting RustedBot...");
 
-    let config_path = std::env::args().nth(1);
+    let config = Config::from_file(config_path);
 
-    if config_path.is_none() {
-        println!("Usage: rustedbot <config_path>");
+    let Some(store) = open_store(&config, options.database_path(&config)) else {
         return ExitCode::FAILURE;
-    }
+    };
 
-    let config = Config::from_file(&config_path.unwrap());
+    let backend = if options.console {
+        Backend::Console
+    } else {
+        Backend::Twitch
+    };
 
-    database::sqlite::migrate(config.trusted_users.clone()).expect("Failed to migrate database.");
-    twitch::irc::init(config.user, config.token, config.channel, config.prefix);
+    twitch::irc::init(Services::new(store, config), backend);
 
     ExitCode::SUCCESS
 }
 
-pub fn init_logger() -> Result<(), fern::InitError> {
+/// Opens and migrates the database at `database_path`. `:memory:` skips
+/// SQLite altogether and keeps everything in memory until the bot stops.
+fn open_store(config: &Config, database_path: &str) -> Option<Store> {
+    if database_path == IN_MEMORY {
+        return Some(Store::new(MemoryStore::default()));
+    }
+
+    let store = match SqliteStore::new(database_path) {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("Failed to open database {database_path}: {e}");
+            return None;
+        }
+    };
+
+    let default_channel = config.channels.first().map(String::as_str);
+    if let Err(e) = store.migrate(default_channel) {
+        log::error!("Failed to migrate database: {e}");
+        return None;
+    }
+
+    Some(Store::new(store))
+}
+
+/// Handles `rustedbot db migrate [--dry-run] <config_path>`.
+fn run_db_command(options: &Options, subcommand: &str, config_path: &str) -> ExitCode {
+    if subcommand != "migrate" {
+        println!("Unknown db command: {subcommand}");
+        return ExitCode::FAILURE;
+    }
+
+    init_logger(true).expect("Failed to initialize logger.");
+
+    let config = Config::from_file(config_path);
+
+    let database_path = options.database_path(&config);
+    let store = match SqliteStore::new(database_path) {
+        Ok(store) => store,
+        Err(e) => {
+            println!("Cannot open {database_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let pending = match store.pending_migrations() {
+        Ok(pending) => pending,
+        Err(e) => {
+            println!("Cannot migrate {database_path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if pending.is_empty() {
+        println!(
+            "{database_path} is up to date (schema version {}).",
+            migrations::latest_version()
+        );
+        return ExitCode::SUCCESS;
+    }
+
+    println!("{} pending migration(s):", pending.len());
+    for migration in &pending {
+        println!("  {}: {}", migration.version, migration.description);
+    }
+
+    if options.dry_run {
+        return ExitCode::SUCCESS;
+    }
+
+    let default_channel = config.channels.first().map(String::as_str);
+    match store.migrate(default_channel) {
+        Ok(()) => {
+            println!("Done.");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            println!("Migration failed: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+pub fn init_logger(console: bool) -> Result<(), fern::InitError> {
     let colors = ColoredLevelConfig::new()
         .debug(Color::Green)
         .info(Color::Blue)
         .warn(Color::Yellow)
         .error(Color::Red);
 
-    fern::Dispatch::new()
+    let dispatch = fern::Dispatch::new()
         .format(move |out, message, record| {
             out.finish(format_args!(
                 "[{}][{}] {}",
@@ -45,9 +197,14 @@ pub fn init_logger() -> Result<(), fern::InitError> {
             ))
         })
         .chain(fern::log_file("rustedbot.log")?)
-        .chain(std::io::stdout())
-        .level(log::LevelFilter::Debug)
-        .apply()?;
+        .level(log::LevelFilter::Debug);
+
+    // In console mode stdout is the chat, so logs only go to the file.
+    if console {
+        dispatch.apply()?;
+    } else {
+        dispatch.chain(std::io::stdout()).apply()?;
+    }
 
     Ok(())
 }
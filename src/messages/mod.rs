@@ -1,3 +1,4 @@
+use crate::commands::Response;
 use crate::services::scryfall;
 use chrono::{Local, Timelike};
 use rand::{seq::SliceRandom, Rng};
@@ -119,7 +120,7 @@ impl Message {
         self.subject.is_some()
     }
 
-    pub async fn get_response(&self) -> String {
+    pub async fn get_response(&self, rng: &mut (impl Rng + Send)) -> Response {
         match &self.subject {
             Some(Subject::OCaml) => {
                 let ocaml = self
@@ -129,21 +130,23 @@ impl Message {
                     .expect("OCaml word not found, but subject was detected");
 
                 if ocaml != "OCaml" {
-                    return format!("@{0} Não é {ocaml}, é OCaml.", self.sender);
+                    return Response::Reply(format!("@{0} Não é {ocaml}, é OCaml.", self.sender));
                 }
             }
             Some(Subject::Magic(card)) => {
                 let response = scryfall::get_card(card.to_string()).await;
 
                 if let Some(response) = response {
-                    return response;
+                    return Response::Message(response);
                 }
 
-                return format!("@{0} Não consegui encontrar o card.", self.sender);
+                return Response::Message(format!(
+                    "@{0} Não consegui encontrar o card.",
+                    self.sender
+                ));
             }
             Some(Subject::Greetings) => {
                 let time_of_day = get_time_of_day();
-                let mut rng = rand::thread_rng();
 
                 let generic = vec![
                     "oi @{{sender}}",
@@ -185,15 +188,17 @@ impl Message {
                     &specific
                 };
 
-                return response_pool
-                    .choose(&mut rng)
+                let greeting = response_pool
+                    .choose(rng)
                     .unwrap_or(&"olá @{{sender}}")
                     .replace("{{sender}}", &self.sender);
+
+                return Response::Message(greeting);
             }
             None => {}
         }
 
-        String::from("")
+        Response::None
     }
 }
 
@@ -0,0 +1,88 @@
+use rusqlite::ErrorCode;
+use std::fmt;
+
+/// Everything that can go wrong talking to the database.
+#[derive(Debug)]
+pub enum StoreError {
+    /// No pooled connection could be opened or freed up in time.
+    Unavailable(r2d2::Error),
+    Sqlite(rusqlite::Error),
+    /// The database file or its directory could not be created.
+    Io(std::io::Error),
+    /// The database was migrated by a newer build of the bot.
+    SchemaTooNew {
+        found: u32,
+        supported: u32,
+    },
+}
+
+/// Whether a failed store call was looking something up or changing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl StoreError {
+    /// Whether retrying later is likely to work, because another connection
+    /// or process was holding the database.
+    pub fn is_busy(&self) -> bool {
+        match self {
+            StoreError::Unavailable(_) => true,
+            StoreError::Sqlite(e) => matches!(
+                e.sqlite_error_code(),
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+            ),
+            StoreError::Io(_) | StoreError::SchemaTooNew { .. } => false,
+        }
+    }
+
+    /// What to tell chat instead of the command's normal reply. A failed
+    /// write may have been partly applied, so the reply does not promise
+    /// that nothing changed.
+    pub fn reply(&self, access: Access) -> &'static str {
+        match (self.is_busy(), access) {
+            (true, _) => "The database is busy right now, try again in a moment.",
+            (false, Access::Read) => {
+                "Something went wrong with the database, so the lookup failed."
+            }
+            (false, Access::Write) => {
+                "Something went wrong with the database, so the change may not have been saved."
+            }
+        }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Unavailable(e) => write!(f, "database unavailable: {e}"),
+            StoreError::Sqlite(e) => write!(f, "{e}"),
+            StoreError::Io(e) => write!(f, "{e}"),
+            StoreError::SchemaTooNew { found, supported } => write!(
+                f,
+                "the database is at schema version {found}, but this build only understands up to {supported}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> StoreError {
+        StoreError::Sqlite(e)
+    }
+}
+
+impl From<std::io::Error> for StoreError {
+    fn from(e: std::io::Error) -> StoreError {
+        StoreError::Io(e)
+    }
+}
+
+impl From<r2d2::Error> for StoreError {
+    fn from(e: r2d2::Error) -> StoreError {
+        StoreError::Unavailable(e)
+    }
+}
@@ -0,0 +1,569 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::commands::cooldown::Cooldown;
+use crate::commands::mode::{ResponseMode, WeightedResponse};
+use crate::commands::role::Role;
+
+use super::error::StoreError;
+use super::store::{
+    AliasStore, CommandStore, CommandUsage, CustomCommand, Revision, TrustStore, UsageStore,
+};
+
+/// A backend that keeps everything in memory and forgets it when dropped.
+/// It behaves like `SqliteStore`, without a database file or SQLite.
+#[derive(Default)]
+pub struct MemoryStore {
+    tables: Mutex<Tables>,
+}
+
+#[derive(Default)]
+struct Tables {
+    /// Every command ever added, deleted ones included, as SQLite keeps them.
+    commands: Vec<StoredCommand>,
+    revisions: Vec<StoredRevision>,
+    /// Targets keyed by alias and scope.
+    aliases: HashMap<(String, Option<String>), String>,
+    /// Usage keyed by channel and command name.
+    usage: HashMap<(String, String), CommandUsage>,
+    /// Usernames paired with the channel they are trusted in.
+    trusted: HashSet<(String, String)>,
+    last_id: i64,
+}
+
+struct StoredCommand {
+    id: i64,
+    name: String,
+    channel: Option<String>,
+    responses: Vec<WeightedResponse>,
+    mode: ResponseMode,
+    role: Role,
+    cooldown: Cooldown,
+    description: Option<String>,
+    turn: u64,
+    created_at: String,
+    deleted: bool,
+}
+
+struct StoredRevision {
+    name: String,
+    channel: Option<String>,
+    revision: Revision,
+}
+
+impl MemoryStore {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap()
+    }
+}
+
+impl Tables {
+    /// The command `name` in exactly `channel`, deleted or not.
+    fn command_mut(&mut self, name: &str, channel: Option<&str>) -> Option<&mut StoredCommand> {
+        self.commands
+            .iter_mut()
+            .find(|command| command.name == name && command.channel.as_deref() == channel)
+    }
+
+    fn live_command_mut(
+        &mut self,
+        name: &str,
+        channel: Option<&str>,
+    ) -> Option<&mut StoredCommand> {
+        self.command_mut(name, channel)
+            .filter(|command| !command.deleted)
+    }
+
+    /// The live command `name` available in `channel`, preferring the
+    /// channel's own over the global one.
+    fn visible_command(&self, name: &str, channel: &str) -> Option<&StoredCommand> {
+        let live = |scope: Option<&str>| {
+            self.commands.iter().find(|command| {
+                command.name == name && command.channel.as_deref() == scope && !command.deleted
+            })
+        };
+
+        live(Some(channel)).or_else(|| live(None))
+    }
+
+    fn record_revision(
+        &mut self,
+        name: &str,
+        channel: Option<&str>,
+        action: &str,
+        old_response: Option<&str>,
+        new_response: Option<&str>,
+        author: &str,
+    ) {
+        let number = self.revisions_of(name, channel).count() as u32 + 1;
+
+        self.revisions.push(StoredRevision {
+            name: name.to_string(),
+            channel: channel.map(String::from),
+            revision: Revision {
+                number,
+                action: action.to_string(),
+                old_response: old_response.map(String::from),
+                new_response: new_response.map(String::from),
+                author: author.to_string(),
+                created_at: now(),
+            },
+        });
+    }
+
+    fn revisions_of<'a>(
+        &'a self,
+        name: &'a str,
+        channel: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Revision> + 'a {
+        self.revisions
+            .iter()
+            .filter(move |stored| stored.name == name && stored.channel.as_deref() == channel)
+            .map(|stored| &stored.revision)
+    }
+
+    fn write_response(
+        &mut self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+        action: &str,
+    ) -> bool {
+        let Some(command) = self.live_command_mut(name, channel) else {
+            return false;
+        };
+
+        let old_response = std::mem::replace(&mut command.responses[0].text, response.to_string());
+        self.record_revision(
+            name,
+            channel,
+            action,
+            Some(&old_response),
+            Some(response),
+            author,
+        );
+
+        true
+    }
+}
+
+impl CommandStore for MemoryStore {
+    fn create_command(
+        &self,
+        name: &str,
+        response: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut tables = self.tables();
+
+        let command = StoredCommand {
+            id: 0,
+            name: name.to_string(),
+            channel: channel.map(String::from),
+            responses: vec![WeightedResponse {
+                text: response.to_string(),
+                weight: 1,
+            }],
+            mode: ResponseMode::default(),
+            role: Role::Everyone,
+            cooldown: Cooldown::default(),
+            description: None,
+            turn: 0,
+            created_at: now(),
+            deleted: false,
+        };
+
+        // Like the unique index in SQLite, a deleted command keeps its id and
+        // is replaced, while a live one is left alone.
+        match tables.command_mut(name, channel) {
+            Some(existing) if existing.deleted => {
+                *existing = StoredCommand {
+                    id: existing.id,
+                    ..command
+                }
+            }
+            Some(_) => return Ok(false),
+            None => {
+                tables.last_id += 1;
+                let id = tables.last_id;
+                tables.commands.push(StoredCommand { id, ..command });
+            }
+        }
+
+        tables.record_revision(name, channel, "add", None, Some(response), author);
+
+        Ok(true)
+    }
+
+    fn get_commands(&self, channel: &str) -> Result<Vec<(String, Role)>, StoreError> {
+        let tables = self.tables();
+
+        let mut commands: Vec<(String, Role)> = tables
+            .commands
+            .iter()
+            .filter(|command| !command.deleted)
+            .filter(|command| match command.channel.as_deref() {
+                Some(scope) => scope == channel,
+                None => tables
+                    .visible_command(&command.name, channel)
+                    .is_some_and(|visible| visible.id == command.id),
+            })
+            .map(|command| (command.name.clone(), command.role))
+            .collect();
+        commands.sort();
+
+        Ok(commands)
+    }
+
+    fn get_command(&self, name: &str, channel: &str) -> Result<Option<CustomCommand>, StoreError> {
+        let tables = self.tables();
+
+        Ok(tables
+            .visible_command(name, channel)
+            .map(|command| CustomCommand {
+                id: command.id,
+                responses: command.responses.clone(),
+                mode: command.mode,
+                role: command.role,
+                cooldown: command.cooldown,
+                description: command.description.clone(),
+                created_at: command.created_at.clone(),
+            }))
+    }
+
+    fn command_exists(&self, name: &str, channel: Option<&str>) -> Result<bool, StoreError> {
+        Ok(self.tables().live_command_mut(name, channel).is_some())
+    }
+
+    fn update_command_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        Ok(self
+            .tables()
+            .write_response(name, channel, response, author, "update"))
+    }
+
+    fn revert_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        Ok(self
+            .tables()
+            .write_response(name, channel, response, author, "revert"))
+    }
+
+    fn delete_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut tables = self.tables();
+
+        let Some(command) = tables.live_command_mut(name, channel) else {
+            return Ok(false);
+        };
+        command.deleted = true;
+        let old_response = command.responses[0].text.clone();
+
+        tables.record_revision(name, channel, "delete", Some(&old_response), None, author);
+
+        Ok(true)
+    }
+
+    fn restore_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut tables = self.tables();
+
+        let Some(command) = tables
+            .command_mut(name, channel)
+            .filter(|command| command.deleted)
+        else {
+            return Ok(false);
+        };
+        command.deleted = false;
+        let response = command.responses[0].text.clone();
+
+        tables.record_revision(name, channel, "restore", None, Some(&response), author);
+
+        Ok(true)
+    }
+
+    fn get_revisions(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Revision>, StoreError> {
+        let tables = self.tables();
+        let revisions: Vec<Revision> = tables.revisions_of(name, channel).cloned().collect();
+
+        Ok(revisions.into_iter().rev().take(limit).collect())
+    }
+
+    fn get_revision(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: u32,
+    ) -> Result<Option<Revision>, StoreError> {
+        let tables = self.tables();
+        let revision = tables
+            .revisions_of(name, channel)
+            .find(|revision| revision.number == number)
+            .cloned();
+
+        Ok(revision)
+    }
+
+    fn set_command_role(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        role: Role,
+    ) -> Result<bool, StoreError> {
+        Ok(update(self, name, channel, |command| command.role = role))
+    }
+
+    fn set_command_description(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<bool, StoreError> {
+        Ok(update(self, name, channel, |command| {
+            command.description = description.map(String::from)
+        }))
+    }
+
+    fn set_command_cooldown(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        cooldown: Cooldown,
+    ) -> Result<bool, StoreError> {
+        Ok(update(self, name, channel, |command| {
+            command.cooldown = cooldown
+        }))
+    }
+
+    fn add_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+    ) -> Result<Option<usize>, StoreError> {
+        let mut tables = self.tables();
+
+        Ok(tables.live_command_mut(name, channel).map(|command| {
+            command.responses.push(WeightedResponse {
+                text: response.to_string(),
+                weight: 1,
+            });
+            command.responses.len()
+        }))
+    }
+
+    fn delete_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+    ) -> Result<bool, StoreError> {
+        let mut tables = self.tables();
+
+        let Some(command) = tables.live_command_mut(name, channel) else {
+            return Ok(false);
+        };
+
+        if number < 2 || number > command.responses.len() {
+            return Ok(false);
+        }
+        command.responses.remove(number - 1);
+
+        Ok(true)
+    }
+
+    fn list_responses(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+    ) -> Result<Option<(ResponseMode, Vec<WeightedResponse>)>, StoreError> {
+        let mut tables = self.tables();
+
+        Ok(tables
+            .live_command_mut(name, channel)
+            .map(|command| (command.mode, command.responses.clone())))
+    }
+
+    fn set_response_mode(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        mode: ResponseMode,
+    ) -> Result<bool, StoreError> {
+        Ok(update(self, name, channel, |command| {
+            command.mode = mode;
+            command.turn = 0;
+        }))
+    }
+
+    fn set_response_weight(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+        weight: u32,
+    ) -> Result<bool, StoreError> {
+        let mut tables = self.tables();
+
+        let response = tables
+            .live_command_mut(name, channel)
+            .and_then(|command| command.responses.get_mut(number.checked_sub(1)?));
+
+        let Some(response) = response else {
+            return Ok(false);
+        };
+        response.weight = weight;
+
+        Ok(true)
+    }
+
+    fn next_turn(&self, id: i64) -> Result<u64, StoreError> {
+        let mut tables = self.tables();
+
+        // SQLite would fail to return a row for an unknown id; there is no
+        // turn to report either way.
+        Ok(tables
+            .commands
+            .iter_mut()
+            .find(|command| command.id == id)
+            .map_or(0, |command| {
+                command.turn += 1;
+                command.turn - 1
+            }))
+    }
+}
+
+impl AliasStore for MemoryStore {
+    fn resolve_alias(&self, alias: &str, channel: &str) -> Result<Option<String>, StoreError> {
+        let tables = self.tables();
+
+        let target = tables
+            .aliases
+            .get(&(alias.to_string(), Some(channel.to_string())))
+            .or_else(|| tables.aliases.get(&(alias.to_string(), None)))
+            .cloned();
+
+        Ok(target)
+    }
+
+    fn get_alias(&self, alias: &str, channel: Option<&str>) -> Result<Option<String>, StoreError> {
+        let key = (alias.to_string(), channel.map(String::from));
+        Ok(self.tables().aliases.get(&key).cloned())
+    }
+
+    fn create_alias(
+        &self,
+        alias: &str,
+        target: &str,
+        channel: Option<&str>,
+    ) -> Result<(), StoreError> {
+        self.tables().aliases.insert(
+            (alias.to_string(), channel.map(String::from)),
+            target.to_string(),
+        );
+
+        Ok(())
+    }
+
+    fn delete_alias(&self, alias: &str, channel: Option<&str>) -> Result<bool, StoreError> {
+        let removed = self
+            .tables()
+            .aliases
+            .remove(&(alias.to_string(), channel.map(String::from)));
+
+        Ok(removed.is_some())
+    }
+}
+
+impl UsageStore for MemoryStore {
+    fn record_use(&self, name: &str, channel: &str, caller: &str) -> Result<u64, StoreError> {
+        let mut tables = self.tables();
+
+        let usage = tables
+            .usage
+            .entry((channel.to_string(), name.to_string()))
+            .or_insert_with(|| CommandUsage {
+                count: 0,
+                last_used_at: String::new(),
+                last_caller: String::new(),
+            });
+        usage.count += 1;
+        usage.last_caller = caller.to_string();
+        usage.last_used_at = now();
+
+        Ok(usage.count)
+    }
+
+    fn get_usage(&self, name: &str, channel: &str) -> Result<Option<CommandUsage>, StoreError> {
+        let tables = self.tables();
+
+        Ok(tables
+            .usage
+            .get(&(channel.to_string(), name.to_string()))
+            .cloned())
+    }
+}
+
+impl TrustStore for MemoryStore {
+    fn is_trusted(&self, username: &str, channel: &str) -> Result<bool, StoreError> {
+        let key = (username.to_string(), channel.to_string());
+        Ok(self.tables().trusted.contains(&key))
+    }
+
+    fn trust_user(&self, username: &str, channel: &str) -> Result<(), StoreError> {
+        let key = (username.to_string(), channel.to_string());
+        self.tables().trusted.insert(key);
+        Ok(())
+    }
+
+    fn untrust_user(&self, username: &str, channel: &str) -> Result<bool, StoreError> {
+        let key = (username.to_string(), channel.to_string());
+        Ok(self.tables().trusted.remove(&key))
+    }
+}
+
+/// Applies `change` to the live command `name`. Returns whether there was one.
+fn update(
+    store: &MemoryStore,
+    name: &str,
+    channel: Option<&str>,
+    change: impl FnOnce(&mut StoredCommand),
+) -> bool {
+    match store.tables().live_command_mut(name, channel) {
+        Some(command) => {
+            change(command);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The current time, formatted like SQLite's `datetime('now')`.
+fn now() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
@@ -0,0 +1,375 @@
+use rusqlite::{Connection, Result};
+
+use super::error::StoreError;
+
+/// A single step of the schema history. Steps are applied once, in order,
+/// each inside its own transaction. Databases created before versioning
+/// start at version 0 and replay every step, so steps must be safe to run
+/// against tables that already have their changes.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Connection, Option<&str>) -> Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create the commands and trusted_users tables",
+        apply: create_base_tables,
+    },
+    Migration {
+        version: 2,
+        description: "scope custom commands to channels",
+        apply: scope_commands_to_channel,
+    },
+    Migration {
+        version: 3,
+        description: "add a minimum role to custom commands",
+        apply: add_command_roles,
+    },
+    Migration {
+        version: 4,
+        description: "add cooldowns to custom commands",
+        apply: add_command_cooldowns,
+    },
+    Migration {
+        version: 5,
+        description: "create the command_usage table",
+        apply: create_command_usage,
+    },
+    Migration {
+        version: 6,
+        description: "create the command_aliases table",
+        apply: create_command_aliases,
+    },
+    Migration {
+        version: 7,
+        description: "create the command_revisions table",
+        apply: create_command_revisions,
+    },
+    Migration {
+        version: 8,
+        description: "support several responses per custom command",
+        apply: add_command_responses,
+    },
+    Migration {
+        version: 9,
+        description: "add descriptions to custom commands",
+        apply: add_command_descriptions,
+    },
+    Migration {
+        version: 10,
+        description: "scope trusted users to channels",
+        apply: scope_trusted_users_to_channel,
+    },
+];
+
+/// The newest schema this build understands.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Returns the migrations that have not been applied to `conn` yet.
+pub fn pending(conn: &Connection) -> Result<Vec<&'static Migration>, StoreError> {
+    const CURRENT_VERSION_QUERY: &str = "
+    	SELECT IFNULL(MAX(version), 0)
+    	FROM schema_version
+    ";
+
+    let current: u32 = if table_exists(conn, "schema_version")? {
+        conn.query_row(CURRENT_VERSION_QUERY, [], |row| row.get(0))?
+    } else {
+        0
+    };
+
+    if current > latest_version() {
+        return Err(StoreError::SchemaTooNew {
+            found: current,
+            supported: latest_version(),
+        });
+    }
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+        .collect())
+}
+
+/// Applies every pending migration. `default_channel` receives the commands
+/// of databases that predate channels.
+pub fn run(conn: &mut Connection, default_channel: Option<&str>) -> Result<(), StoreError> {
+    const RECORD_VERSION_QUERY: &str = "
+    	INSERT INTO schema_version (version, description, applied_at)
+    	VALUES (?1, ?2, datetime('now'))
+    ";
+
+    const CREATE_SCHEMA_VERSION_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS schema_version (
+    		version INTEGER PRIMARY KEY,
+    		description TEXT NOT NULL,
+    		applied_at DATETIME NOT NULL
+    	)
+    ";
+
+    let pending = pending(conn)?;
+    create_table(conn, CREATE_SCHEMA_VERSION_TABLE)?;
+
+    for migration in pending {
+        let transaction = conn.transaction()?;
+        (migration.apply)(&transaction, default_channel)?;
+        transaction.execute(
+            RECORD_VERSION_QUERY,
+            rusqlite::params![migration.version, migration.description],
+        )?;
+        transaction.commit()?;
+
+        log::info!(
+            "Applied migration {}: {}",
+            migration.version,
+            migration.description
+        );
+    }
+
+    Ok(())
+}
+
+fn create_base_tables(conn: &Connection, _: Option<&str>) -> Result<()> {
+    const CREATE_COMMANDS_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS commands (
+    		id INTEGER PRIMARY KEY,
+    		name TEXT UNIQUE NOT NULL,
+    		response TEXT NOT NULL,
+    		created_at DATETIME NOT NULL,
+    		updated_at DATETIME NOT NULL,
+    		deleted_at DATETIME
+    	)
+    ";
+
+    const CREATE_TRUSTED_USERS_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS trusted_users (
+    		id INTEGER PRIMARY KEY,
+    		username TEXT UNIQUE NOT NULL,
+    		created_at DATETIME NOT NULL,
+    		updated_at DATETIME NOT NULL,
+    		deleted_at DATETIME
+    	)
+    ";
+
+    create_table(conn, CREATE_COMMANDS_TABLE)?;
+    create_table(conn, CREATE_TRUSTED_USERS_TABLE)
+}
+
+/// Rebuilds the pre-channel `commands` table, moving every existing row to
+/// `channel`. The table has to be recreated because SQLite cannot drop the
+/// old `UNIQUE` constraint on `name` in place.
+fn scope_commands_to_channel(conn: &Connection, channel: Option<&str>) -> Result<()> {
+    const CREATE_COMMANDS_TABLE: &str = "
+    	CREATE TABLE commands (
+    		id INTEGER PRIMARY KEY,
+    		name TEXT NOT NULL,
+    		channel TEXT,
+    		response TEXT NOT NULL,
+    		created_at DATETIME NOT NULL,
+    		updated_at DATETIME NOT NULL,
+    		deleted_at DATETIME
+    	)
+    ";
+
+    const CREATE_COMMANDS_INDEX: &str = "
+    	CREATE UNIQUE INDEX IF NOT EXISTS commands_name_channel
+    	ON commands (name, IFNULL(channel, ''))
+    ";
+
+    const COPY_COMMANDS_QUERY: &str = "
+    	INSERT INTO commands (id, name, channel, response, created_at, updated_at, deleted_at)
+    	SELECT id, name, ?, response, created_at, updated_at, deleted_at
+    	FROM commands_unscoped
+    ";
+
+    if !has_column(conn, "commands", "channel")? {
+        let moved: i64 = conn.query_row("SELECT COUNT(*) FROM commands", [], |row| row.get(0))?;
+
+        conn.execute("ALTER TABLE commands RENAME TO commands_unscoped", [])?;
+        conn.execute(CREATE_COMMANDS_TABLE, [])?;
+        conn.execute(COPY_COMMANDS_QUERY, [channel])?;
+        conn.execute("DROP TABLE commands_unscoped", [])?;
+
+        if moved > 0 {
+            log::info!(
+                "Migrated existing custom commands to {}",
+                channel.map_or(String::from("the global scope"), |c| format!("#{c}"))
+            );
+        }
+    }
+
+    create_table(conn, CREATE_COMMANDS_INDEX)
+}
+
+fn add_command_roles(conn: &Connection, _: Option<&str>) -> Result<()> {
+    add_column(conn, "commands", "role", "TEXT NOT NULL DEFAULT 'everyone'")
+}
+
+fn add_command_cooldowns(conn: &Connection, _: Option<&str>) -> Result<()> {
+    add_column(conn, "commands", "cooldown", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column(
+        conn,
+        "commands",
+        "user_cooldown",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+}
+
+fn create_command_usage(conn: &Connection, _: Option<&str>) -> Result<()> {
+    const CREATE_COMMAND_USAGE_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS command_usage (
+    		id INTEGER PRIMARY KEY,
+    		channel TEXT NOT NULL,
+    		name TEXT NOT NULL,
+    		count INTEGER NOT NULL DEFAULT 0,
+    		last_caller TEXT NOT NULL,
+    		last_used_at DATETIME NOT NULL,
+    		UNIQUE (channel, name)
+    	)
+    ";
+
+    create_table(conn, CREATE_COMMAND_USAGE_TABLE)
+}
+
+fn create_command_aliases(conn: &Connection, _: Option<&str>) -> Result<()> {
+    const CREATE_COMMAND_ALIASES_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS command_aliases (
+    		id INTEGER PRIMARY KEY,
+    		alias TEXT NOT NULL,
+    		channel TEXT,
+    		target TEXT NOT NULL,
+    		created_at DATETIME NOT NULL
+    	)
+    ";
+
+    const CREATE_COMMAND_ALIASES_INDEX: &str = "
+    	CREATE UNIQUE INDEX IF NOT EXISTS command_aliases_alias_channel
+    	ON command_aliases (alias, IFNULL(channel, ''))
+    ";
+
+    create_table(conn, CREATE_COMMAND_ALIASES_TABLE)?;
+    create_table(conn, CREATE_COMMAND_ALIASES_INDEX)
+}
+
+fn create_command_revisions(conn: &Connection, _: Option<&str>) -> Result<()> {
+    const CREATE_COMMAND_REVISIONS_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS command_revisions (
+    		id INTEGER PRIMARY KEY,
+    		name TEXT NOT NULL,
+    		channel TEXT,
+    		action TEXT NOT NULL,
+    		old_response TEXT,
+    		new_response TEXT,
+    		author TEXT NOT NULL,
+    		created_at DATETIME NOT NULL
+    	)
+    ";
+
+    create_table(conn, CREATE_COMMAND_REVISIONS_TABLE)
+}
+
+fn add_command_responses(conn: &Connection, _: Option<&str>) -> Result<()> {
+    const CREATE_COMMAND_RESPONSES_TABLE: &str = "
+    	CREATE TABLE IF NOT EXISTS command_responses (
+    		id INTEGER PRIMARY KEY,
+    		command_id INTEGER NOT NULL REFERENCES commands (id),
+    		response TEXT NOT NULL,
+    		weight INTEGER NOT NULL DEFAULT 1,
+    		created_at DATETIME NOT NULL
+    	)
+    ";
+
+    add_column(conn, "commands", "mode", "TEXT NOT NULL DEFAULT 'random'")?;
+    add_column(conn, "commands", "weight", "INTEGER NOT NULL DEFAULT 1")?;
+    add_column(conn, "commands", "turn", "INTEGER NOT NULL DEFAULT 0")?;
+    create_table(conn, CREATE_COMMAND_RESPONSES_TABLE)
+}
+
+fn add_command_descriptions(conn: &Connection, _: Option<&str>) -> Result<()> {
+    add_column(conn, "commands", "description", "TEXT")
+}
+
+/// Rebuilds `trusted_users` so trust granted with `!trust` only applies in
+/// the channel it was granted in. Existing rows move to `channel`, like the
+/// commands did; without one they stay unscoped and no longer trust anyone.
+fn scope_trusted_users_to_channel(conn: &Connection, channel: Option<&str>) -> Result<()> {
+    const CREATE_TRUSTED_USERS_TABLE: &str = "
+    	CREATE TABLE trusted_users (
+    		id INTEGER PRIMARY KEY,
+    		username TEXT NOT NULL,
+    		channel TEXT,
+    		created_at DATETIME NOT NULL,
+    		updated_at DATETIME NOT NULL,
+    		deleted_at DATETIME
+    	)
+    ";
+
+    const CREATE_TRUSTED_USERS_INDEX: &str = "
+    	CREATE UNIQUE INDEX IF NOT EXISTS trusted_users_username_channel
+    	ON trusted_users (username, IFNULL(channel, ''))
+    ";
+
+    const COPY_TRUSTED_USERS_QUERY: &str = "
+    	INSERT INTO trusted_users (id, username, channel, created_at, updated_at, deleted_at)
+    	SELECT id, username, ?, created_at, updated_at, deleted_at
+    	FROM trusted_users_unscoped
+    ";
+
+    if !has_column(conn, "trusted_users", "channel")? {
+        conn.execute(
+            "ALTER TABLE trusted_users RENAME TO trusted_users_unscoped",
+            [],
+        )?;
+        conn.execute(CREATE_TRUSTED_USERS_TABLE, [])?;
+        conn.execute(COPY_TRUSTED_USERS_QUERY, [channel])?;
+        conn.execute("DROP TABLE trusted_users_unscoped", [])?;
+    }
+
+    create_table(conn, CREATE_TRUSTED_USERS_INDEX)
+}
+
+fn create_table(conn: &Connection, ddl: &str) -> Result<()> {
+    conn.execute(ddl, [])?;
+    Ok(())
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    const TABLE_EXISTS_QUERY: &str = "
+    	SELECT 1
+    	FROM sqlite_master
+    	WHERE type = 'table'
+    	AND name = ?
+    ";
+
+    let mut statement = conn.prepare(TABLE_EXISTS_QUERY)?;
+    statement.exists([table])
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut statement = conn.prepare(&format!("PRAGMA table_info({table})"))?;
+    let columns = statement.query_map([], |row| row.get::<usize, String>(1))?;
+
+    for name in columns {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
@@ -1 +1,5 @@
+pub mod error;
+pub mod memory;
+pub mod migrations;
 pub mod sqlite;
+pub mod store;
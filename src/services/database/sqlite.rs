@@ -1,195 +1,952 @@
-use rusqlite::{params_from_iter, Connection, Result};
+use r2d2::{Pool, PooledConnection};
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::commands::cooldown::Cooldown;
+use crate::commands::mode::{ResponseMode, WeightedResponse};
+use crate::commands::role::Role;
+
+use super::error::StoreError;
+use super::migrations::{self, Migration};
+use super::store::{
+    AliasStore, CommandStore, CommandUsage, CustomCommand, Revision, TrustStore, UsageStore,
+};
+
+/// Path that keeps the database in memory, for tests and throwaway sessions.
+pub const IN_MEMORY: &str = ":memory:";
+
+impl CustomCommand {
+    fn from_row(row: &Row) -> Result<CustomCommand> {
+        let role: String = row.get("role")?;
+        let cooldown: u64 = row.get("cooldown")?;
+        let user_cooldown: u64 = row.get("user_cooldown")?;
+
+        let mode: String = row.get("mode")?;
+
+        Ok(CustomCommand {
+            id: row.get("id")?,
+            responses: vec![WeightedResponse {
+                text: row.get("response")?,
+                weight: row.get("weight")?,
+            }],
+            mode: mode.parse().unwrap_or_else(|e| {
+                log::warn!("Invalid response mode stored for a custom command: {e}");
+                ResponseMode::Random
+            }),
+            role: role.parse().unwrap_or_else(|e| {
+                log::warn!("Invalid role stored for a custom command: {e}");
+                Role::Everyone
+            }),
+            cooldown: Cooldown::from_secs(cooldown, user_cooldown),
+            description: row.get("description")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
 
-const DATABASE_PATH: &str = "./database/rusted.db";
+impl Revision {
+    fn from_row(row: &Row) -> Result<Revision> {
+        Ok(Revision {
+            number: row.get("number")?,
+            action: row.get("action")?,
+            old_response: row.get("old_response")?,
+            new_response: row.get("new_response")?,
+            author: row.get("author")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
 
-pub fn migrate(trusted_users: Vec<String>) -> Result<()> {
-    let connection = Connection::open(DATABASE_PATH)?;
+/// How long a connection waits on a locked database before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const POOL_SIZE: u32 = 4;
 
-    const CREATE_COMMANDS_TABLE: &str = "
-		CREATE TABLE IF NOT EXISTS commands (
-			id INTEGER PRIMARY KEY,
-			name TEXT UNIQUE NOT NULL,
-			response TEXT NOT NULL,
-			created_at DATETIME NOT NULL,
-			updated_at DATETIME NOT NULL,
-			deleted_at DATETIME
-		)
-	";
+/// Opens pooled connections to the database file, each in WAL mode so
+/// readers are not blocked by the bot's own writes.
+#[derive(Debug)]
+struct ConnectionManager {
+    path: PathBuf,
+}
 
-    const CREATE_TRUSTED_USERS_TABLE: &str = "
-		CREATE TABLE IF NOT EXISTS trusted_users (
-			id INTEGER PRIMARY KEY,
-			username TEXT UNIQUE NOT NULL,
-			created_at DATETIME NOT NULL,
-			updated_at DATETIME NOT NULL,
-			deleted_at DATETIME
-		)
-	";
+impl r2d2::ManageConnection for ConnectionManager {
+    type Connection = Connection;
+    type Error = rusqlite::Error;
 
-    create_table(&connection, CREATE_COMMANDS_TABLE)?;
-    create_table(&connection, CREATE_TRUSTED_USERS_TABLE)?;
+    fn connect(&self) -> Result<Connection> {
+        let connection = Connection::open(&self.path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(connection)
+    }
 
-    if !trusted_users.is_empty() {
-        insert_trusted_users(&connection, &trusted_users)?;
+    fn is_valid(&self, connection: &mut Connection) -> Result<()> {
+        connection.execute_batch("SELECT 1")
     }
 
-    Ok(())
+    fn has_broken(&self, _connection: &mut Connection) -> bool {
+        false
+    }
 }
 
-fn create_table(conn: &Connection, ddl: &str) -> Result<()> {
-    conn.execute(ddl, [])?;
-    Ok(())
+/// The SQLite backend. Every query draws a connection from a shared pool.
+#[derive(Debug)]
+pub struct SqliteStore {
+    pool: Pool<ConnectionManager>,
 }
 
-fn insert_trusted_users(conn: &Connection, users: &[String]) -> Result<()> {
-    let placeholders = (1..=users.len())
-        .map(|i| format!("(?{i}, datetime('now'), datetime('now'))"))
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let query = format!(
-        "
-        INSERT INTO trusted_users (username, created_at, updated_at)
-        VALUES {placeholders}
-        ON CONFLICT(username) DO UPDATE SET
-            deleted_at = NULL,
-            updated_at = datetime('now')
-        "
-    );
-
-    conn.execute(&query, params_from_iter(users.iter().map(|u| u.as_str())))?;
-    Ok(())
-}
+impl SqliteStore {
+    /// Opens the database at `path`, creating its directory if needed.
+    /// `IN_MEMORY` gives a fresh database that lives as long as the store.
+    pub fn new(path: impl Into<PathBuf>) -> Result<SqliteStore, StoreError> {
+        let path = path.into();
+
+        // Every connection to `:memory:` is a separate database, so the pool
+        // holds a single connection that is never recycled.
+        let builder = if path.as_os_str() == IN_MEMORY {
+            Pool::builder()
+                .max_size(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            Pool::builder().max_size(POOL_SIZE)
+        };
+
+        let pool = builder.build(ConnectionManager { path })?;
+        Ok(SqliteStore { pool })
+    }
 
-pub fn create_command(name: &str, response: &str) {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+    fn open(&self) -> Result<PooledConnection<ConnectionManager>, StoreError> {
+        Ok(self.pool.get()?)
+    }
 
-    const CREATE_COMMAND_QUERY: &str = "
-    	INSERT INTO commands (name, response, created_at, updated_at)
-    	VALUES (?1, ?2, datetime('now'), datetime('now'))
-    ";
+    /// Brings the schema up to date. Fails without touching anything when the
+    /// database is newer than this build.
+    pub fn migrate(&self, default_channel: Option<&str>) -> Result<(), StoreError> {
+        let mut connection = self.open()?;
+        migrations::run(&mut connection, default_channel)
+    }
 
-    connection
-        .execute(CREATE_COMMAND_QUERY, [&name, &response])
-        .unwrap();
+    /// Lists the migrations `migrate` would apply, without applying them.
+    pub fn pending_migrations(&self) -> Result<Vec<&'static Migration>, StoreError> {
+        let connection = self.open()?;
+        migrations::pending(&connection)
+    }
+
+    fn write_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+        action: &str,
+    ) -> Result<bool, StoreError> {
+        let mut connection = self.open()?;
+        let transaction = connection.transaction()?;
+
+        const UPDATE_COMMAND_RESPONSE_QUERY: &str = "
+        	UPDATE commands
+        	SET response = ?1, updated_at = datetime('now')
+        	WHERE name = ?2
+        	AND channel IS ?3
+        	AND deleted_at IS NULL
+        ";
+
+        let Some(old_response) = live_response(&transaction, name, channel)? else {
+            return Ok(false);
+        };
+        transaction.execute(
+            UPDATE_COMMAND_RESPONSE_QUERY,
+            params![response, name, channel],
+        )?;
+        record_revision(
+            &transaction,
+            name,
+            channel,
+            action,
+            Some(&old_response),
+            Some(response),
+            author,
+        )?;
+        transaction.commit()?;
+
+        Ok(true)
+    }
 }
 
-pub fn get_commands() -> Result<Vec<(String, String)>, rusqlite::Error> {
-    let connection = Connection::open(DATABASE_PATH)?;
+impl CommandStore for SqliteStore {
+    fn create_command(
+        &self,
+        name: &str,
+        response: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut connection = self.open()?;
+        let transaction = connection.transaction()?;
+
+        // A soft-deleted command with the same name still holds the unique
+        // index, so re-adding it replaces that row with a fresh command.
+        const CREATE_COMMAND_QUERY: &str = "
+        	INSERT INTO commands (name, channel, response, created_at, updated_at)
+        	VALUES (?1, ?2, ?3, datetime('now'), datetime('now'))
+        	ON CONFLICT (name, IFNULL(channel, '')) DO UPDATE
+        	SET response = ?3,
+        		role = 'everyone',
+        		cooldown = 0,
+        		user_cooldown = 0,
+        		mode = 'random',
+        		weight = 1,
+        		turn = 0,
+        		description = NULL,
+        		created_at = datetime('now'),
+        		updated_at = datetime('now'),
+        		deleted_at = NULL
+        	WHERE deleted_at IS NOT NULL
+        ";
+
+        const CLEAR_RESPONSES_QUERY: &str = "
+        	DELETE FROM command_responses
+        	WHERE command_id = (SELECT id FROM commands WHERE name = ?1 AND channel IS ?2)
+        ";
+
+        // A live command is left alone, and so are its responses and history.
+        if transaction.execute(CREATE_COMMAND_QUERY, params![name, channel, response])? == 0 {
+            return Ok(false);
+        }
 
-    const GET_COMMANDS_QUERY: &str = "
-     	SELECT name, response
-    	FROM commands
-    	WHERE deleted_at IS NULL
-    ";
+        transaction.execute(CLEAR_RESPONSES_QUERY, params![name, channel])?;
+        record_revision(
+            &transaction,
+            name,
+            channel,
+            "add",
+            None,
+            Some(response),
+            author,
+        )?;
+        transaction.commit()?;
+
+        Ok(true)
+    }
 
-    let mut statement = connection.prepare(GET_COMMANDS_QUERY)?;
-    let commands = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    fn get_commands(&self, channel: &str) -> Result<Vec<(String, Role)>, StoreError> {
+        let connection = self.open()?;
+
+        const GET_COMMANDS_QUERY: &str = "
+         	SELECT name, role
+        	FROM commands AS c
+        	WHERE deleted_at IS NULL
+        	AND (
+        		channel = ?1
+        		OR (
+        			channel IS NULL
+        			AND NOT EXISTS (
+        				SELECT 1
+        				FROM commands AS o
+        				WHERE o.name = c.name
+        				AND o.channel = ?1
+        				AND o.deleted_at IS NULL
+        			)
+        		)
+        	)
+        	ORDER BY name
+        ";
+
+        let mut statement = connection.prepare(GET_COMMANDS_QUERY)?;
+        let commands = statement.query_map([channel], |row| {
+            let role: String = row.get(1)?;
+            let role = role.parse().unwrap_or_else(|e| {
+                log::warn!("Invalid role stored for a custom command: {e}");
+                Role::Everyone
+            });
+            Ok((row.get(0)?, role))
+        })?;
+
+        let mut result = Vec::new();
+        for command in commands {
+            result.push(command?);
+        }
 
-    let mut result = Vec::new();
-    for command in commands {
-        result.push(command?);
+        Ok(result)
     }
 
-    Ok(result)
-}
+    fn get_command(&self, name: &str, channel: &str) -> Result<Option<CustomCommand>, StoreError> {
+        let connection = self.open()?;
+
+        const GET_COMMAND_QUERY: &str = "
+        	SELECT id, response, weight, mode, role, cooldown, user_cooldown, description, created_at
+        	FROM commands
+        	WHERE name = ?1
+        	AND (channel = ?2 OR channel IS NULL)
+        	AND deleted_at IS NULL
+        	ORDER BY channel IS NULL
+        	LIMIT 1
+        ";
+
+        let command = connection
+            .query_row(GET_COMMAND_QUERY, [name, channel], CustomCommand::from_row)
+            .optional()?;
+
+        let Some(mut command) = command else {
+            return Ok(None);
+        };
+        command
+            .responses
+            .extend(extra_responses(&connection, command.id)?);
+
+        Ok(Some(command))
+    }
 
-pub fn get_command_response(name: &str) -> Result<String, rusqlite::Error> {
-    let connection = Connection::open(DATABASE_PATH)?;
+    fn command_exists(&self, name: &str, channel: Option<&str>) -> Result<bool, StoreError> {
+        let connection = self.open()?;
 
-    const GET_COMMAND_RESPONSE_QUERY: &str = "
-    	SELECT response
-    	FROM commands
-    	WHERE name = ?
-    	AND deleted_at IS NULL
-    ";
+        const COMMAND_EXISTS_QUERY: &str = "
+        	SELECT 1
+        	FROM commands
+        	WHERE name = ?1
+        	AND channel IS ?2
+        	AND deleted_at IS NULL
+        	LIMIT 1
+        ";
 
-    let mut statement = connection.prepare(GET_COMMAND_RESPONSE_QUERY)?;
-    let responses = statement.query_map([&name], |row| Ok(row.get(0)?));
+        let mut statement = connection.prepare(COMMAND_EXISTS_QUERY)?;
+        Ok(statement.exists(params![name, channel])?)
+    }
 
-    match responses {
-        Ok(mut responses) => {
-            if let Some(response) = responses.next() {
-                return Ok(response?);
-            }
+    fn update_command_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        self.write_response(name, channel, response, author, "update")
+    }
+
+    fn revert_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        self.write_response(name, channel, response, author, "revert")
+    }
+
+    fn delete_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut connection = self.open()?;
+        let transaction = connection.transaction()?;
+
+        const DELETE_COMMAND_QUERY: &str = "
+        	UPDATE commands
+        	SET deleted_at = datetime('now')
+        	WHERE name = ?1
+        	AND channel IS ?2
+        	AND deleted_at IS NULL
+        ";
+
+        let Some(old_response) = live_response(&transaction, name, channel)? else {
+            return Ok(false);
+        };
+
+        transaction.execute(DELETE_COMMAND_QUERY, params![name, channel])?;
+        record_revision(
+            &transaction,
+            name,
+            channel,
+            "delete",
+            Some(&old_response),
+            None,
+            author,
+        )?;
+        transaction.commit()?;
+
+        Ok(true)
+    }
+
+    fn restore_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError> {
+        let mut connection = self.open()?;
+        let transaction = connection.transaction()?;
+
+        const RESTORE_COMMAND_QUERY: &str = "
+        	UPDATE commands
+        	SET deleted_at = NULL, updated_at = datetime('now')
+        	WHERE name = ?1
+        	AND channel IS ?2
+        	AND deleted_at IS NOT NULL
+        	RETURNING response
+        ";
+
+        let restored: Option<String> = transaction
+            .query_row(RESTORE_COMMAND_QUERY, params![name, channel], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        let Some(response) = restored else {
+            return Ok(false);
+        };
+
+        record_revision(
+            &transaction,
+            name,
+            channel,
+            "restore",
+            None,
+            Some(&response),
+            author,
+        )?;
+        transaction.commit()?;
+
+        Ok(true)
+    }
+
+    fn get_revisions(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Revision>, StoreError> {
+        let connection = self.open()?;
+
+        const GET_REVISIONS_QUERY: &str = "
+        	SELECT number, action, old_response, new_response, author, created_at
+        	FROM (
+        		SELECT *, ROW_NUMBER() OVER (ORDER BY id) AS number
+        		FROM command_revisions
+        		WHERE name = ?1
+        		AND channel IS ?2
+        	)
+        	ORDER BY number DESC
+        	LIMIT ?3
+        ";
+
+        let mut statement = connection.prepare(GET_REVISIONS_QUERY)?;
+        let revisions = statement.query_map(params![name, channel, limit], Revision::from_row)?;
+
+        Ok(revisions.collect::<Result<_>>()?)
+    }
+
+    fn get_revision(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: u32,
+    ) -> Result<Option<Revision>, StoreError> {
+        let connection = self.open()?;
+
+        const GET_REVISION_QUERY: &str = "
+        	SELECT number, action, old_response, new_response, author, created_at
+        	FROM (
+        		SELECT *, ROW_NUMBER() OVER (ORDER BY id) AS number
+        		FROM command_revisions
+        		WHERE name = ?1
+        		AND channel IS ?2
+        	)
+        	WHERE number = ?3
+        ";
+
+        let revision = connection
+            .query_row(
+                GET_REVISION_QUERY,
+                params![name, channel, number],
+                Revision::from_row,
+            )
+            .optional()?;
+
+        Ok(revision)
+    }
+
+    fn set_command_role(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        role: Role,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const SET_COMMAND_ROLE_QUERY: &str = "
+        	UPDATE commands
+        	SET role = ?1, updated_at = datetime('now')
+        	WHERE name = ?2
+        	AND channel IS ?3
+        	AND deleted_at IS NULL
+        ";
+
+        let updated =
+            connection.execute(SET_COMMAND_ROLE_QUERY, params![role.name(), name, channel])?;
+
+        Ok(updated > 0)
+    }
+
+    fn set_command_description(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const SET_COMMAND_DESCRIPTION_QUERY: &str = "
+        	UPDATE commands
+        	SET description = ?1, updated_at = datetime('now')
+        	WHERE name = ?2
+        	AND channel IS ?3
+        	AND deleted_at IS NULL
+        ";
+
+        let updated = connection.execute(
+            SET_COMMAND_DESCRIPTION_QUERY,
+            params![description, name, channel],
+        )?;
+
+        Ok(updated > 0)
+    }
+
+    fn set_command_cooldown(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        cooldown: Cooldown,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const SET_COMMAND_COOLDOWN_QUERY: &str = "
+        	UPDATE commands
+        	SET cooldown = ?1, user_cooldown = ?2, updated_at = datetime('now')
+        	WHERE name = ?3
+        	AND channel IS ?4
+        	AND deleted_at IS NULL
+        ";
+
+        let updated = connection.execute(
+            SET_COMMAND_COOLDOWN_QUERY,
+            params![
+                cooldown.global.as_secs(),
+                cooldown.per_user.as_secs(),
+                name,
+                channel
+            ],
+        )?;
+
+        Ok(updated > 0)
+    }
+
+    fn add_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+    ) -> Result<Option<usize>, StoreError> {
+        let connection = self.open()?;
+
+        const ADD_RESPONSE_QUERY: &str = "
+        	INSERT INTO command_responses (command_id, response, created_at)
+        	VALUES (?1, ?2, datetime('now'))
+        ";
+
+        let Some(id) = live_command_id(&connection, name, channel)? else {
+            return Ok(None);
+        };
+        connection.execute(ADD_RESPONSE_QUERY, params![id, response])?;
+
+        Ok(Some(1 + extra_responses(&connection, id)?.len()))
+    }
+
+    fn delete_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const DELETE_RESPONSE_QUERY: &str = "
+        	DELETE FROM command_responses
+        	WHERE id = (
+        		SELECT id
+        		FROM command_responses
+        		WHERE command_id = ?1
+        		ORDER BY id
+        		LIMIT 1 OFFSET ?2
+        	)
+        ";
+
+        let Some(id) = live_command_id(&connection, name, channel)? else {
+            return Ok(false);
+        };
+
+        if number < 2 {
+            return Ok(false);
         }
-        Err(e) => return Err(e),
+
+        let deleted = connection.execute(DELETE_RESPONSE_QUERY, params![id, number - 2])?;
+
+        Ok(deleted > 0)
     }
 
-    Err(rusqlite::Error::QueryReturnedNoRows)
-}
+    fn list_responses(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+    ) -> Result<Option<(ResponseMode, Vec<WeightedResponse>)>, StoreError> {
+        let connection = self.open()?;
+
+        const MAIN_RESPONSE_QUERY: &str = "
+        	SELECT id, response, weight, mode
+        	FROM commands
+        	WHERE name = ?1
+        	AND channel IS ?2
+        	AND deleted_at IS NULL
+        ";
+
+        let command = connection
+            .query_row(MAIN_RESPONSE_QUERY, params![name, channel], |row| {
+                let mode: String = row.get("mode")?;
+                let main = WeightedResponse {
+                    text: row.get("response")?,
+                    weight: row.get("weight")?,
+                };
+                Ok((row.get::<_, i64>("id")?, main, mode))
+            })
+            .optional()?;
+
+        let Some((id, main, mode)) = command else {
+            return Ok(None);
+        };
+
+        let mut responses = vec![main];
+        responses.extend(extra_responses(&connection, id)?);
+
+        Ok(Some((mode.parse().unwrap_or_default(), responses)))
+    }
 
-pub fn update_command_response(name: &str, response: &str) {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+    fn set_response_mode(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        mode: ResponseMode,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const SET_RESPONSE_MODE_QUERY: &str = "
+        	UPDATE commands
+        	SET mode = ?1, turn = 0, updated_at = datetime('now')
+        	WHERE name = ?2
+        	AND channel IS ?3
+        	AND deleted_at IS NULL
+        ";
+
+        let updated =
+            connection.execute(SET_RESPONSE_MODE_QUERY, params![mode.name(), name, channel])?;
+
+        Ok(updated > 0)
+    }
 
-    const UPDATE_COMMAND_RESPONSE_QUERY: &str = "
-    	UPDATE commands
-    	SET response = ?, updated_at = datetime('now')
-    	WHERE name = ?
-    ";
+    fn set_response_weight(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+        weight: u32,
+    ) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const SET_MAIN_WEIGHT_QUERY: &str = "
+        	UPDATE commands
+        	SET weight = ?1, updated_at = datetime('now')
+        	WHERE id = ?2
+        ";
+
+        const SET_EXTRA_WEIGHT_QUERY: &str = "
+        	UPDATE command_responses
+        	SET weight = ?1
+        	WHERE id = (
+        		SELECT id
+        		FROM command_responses
+        		WHERE command_id = ?2
+        		ORDER BY id
+        		LIMIT 1 OFFSET ?3
+        	)
+        ";
+
+        let Some(id) = live_command_id(&connection, name, channel)? else {
+            return Ok(false);
+        };
+
+        let updated = match number {
+            0 => 0,
+            1 => connection.execute(SET_MAIN_WEIGHT_QUERY, params![weight, id])?,
+            _ => connection.execute(SET_EXTRA_WEIGHT_QUERY, params![weight, id, number - 2])?,
+        };
+
+        Ok(updated > 0)
+    }
+
+    fn next_turn(&self, id: i64) -> Result<u64, StoreError> {
+        let connection = self.open()?;
+
+        const NEXT_TURN_QUERY: &str = "
+        	UPDATE commands
+        	SET turn = turn + 1
+        	WHERE id = ?1
+        	RETURNING turn - 1
+        ";
 
-    connection
-        .execute(UPDATE_COMMAND_RESPONSE_QUERY, [&response, &name])
-        .unwrap();
+        Ok(connection.query_row(NEXT_TURN_QUERY, params![id], |row| row.get(0))?)
+    }
 }
 
-pub fn delete_command(name: &str) {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+impl AliasStore for SqliteStore {
+    fn resolve_alias(&self, alias: &str, channel: &str) -> Result<Option<String>, StoreError> {
+        let connection = self.open()?;
+
+        const RESOLVE_ALIAS_QUERY: &str = "
+        	SELECT target
+        	FROM command_aliases
+        	WHERE alias = ?1
+        	AND (channel = ?2 OR channel IS NULL)
+        	ORDER BY channel IS NULL
+        	LIMIT 1
+        ";
+
+        let target = connection
+            .query_row(RESOLVE_ALIAS_QUERY, params![alias, channel], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        Ok(target)
+    }
 
-    const DELETE_COMMAND_QUERY: &str = "
-    	UPDATE commands
-    	SET deleted_at = datetime('now')
-    	WHERE name = ?
-    ";
+    fn get_alias(&self, alias: &str, channel: Option<&str>) -> Result<Option<String>, StoreError> {
+        let connection = self.open()?;
 
-    connection.execute(DELETE_COMMAND_QUERY, [&name]).unwrap();
+        const GET_ALIAS_QUERY: &str = "
+        	SELECT target
+        	FROM command_aliases
+        	WHERE alias = ?1
+        	AND channel IS ?2
+        ";
+
+        let target = connection
+            .query_row(GET_ALIAS_QUERY, params![alias, channel], |row| row.get(0))
+            .optional()?;
+
+        Ok(target)
+    }
+
+    fn create_alias(
+        &self,
+        alias: &str,
+        target: &str,
+        channel: Option<&str>,
+    ) -> Result<(), StoreError> {
+        let connection = self.open()?;
+
+        const CREATE_ALIAS_QUERY: &str = "
+        	INSERT INTO command_aliases (alias, channel, target, created_at)
+        	VALUES (?1, ?2, ?3, datetime('now'))
+        	ON CONFLICT (alias, IFNULL(channel, '')) DO UPDATE
+        	SET target = ?3, created_at = datetime('now')
+        ";
+
+        connection.execute(CREATE_ALIAS_QUERY, params![alias, channel, target])?;
+
+        Ok(())
+    }
+
+    fn delete_alias(&self, alias: &str, channel: Option<&str>) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const DELETE_ALIAS_QUERY: &str = "
+        	DELETE FROM command_aliases
+        	WHERE alias = ?1
+        	AND channel IS ?2
+        ";
+
+        let deleted = connection.execute(DELETE_ALIAS_QUERY, params![alias, channel])?;
+
+        Ok(deleted > 0)
+    }
 }
 
-pub fn is_trusted(username: &str) -> bool {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+impl UsageStore for SqliteStore {
+    fn record_use(&self, name: &str, channel: &str, caller: &str) -> Result<u64, StoreError> {
+        let connection = self.open()?;
 
-    const IS_TRUSTED_QUERY: &str = "
-    	SELECT 1
-    	FROM trusted_users
-    	WHERE username = ?
-    	AND deleted_at IS NULL
-    	LIMIT 1
-	";
+        const RECORD_USE_QUERY: &str = "
+        	INSERT INTO command_usage (channel, name, count, last_caller, last_used_at)
+        	VALUES (?1, ?2, 1, ?3, datetime('now'))
+        	ON CONFLICT (channel, name) DO UPDATE
+        	SET count = count + 1, last_caller = ?3, last_used_at = datetime('now')
+        	RETURNING count
+        ";
+
+        let count =
+            connection.query_row(RECORD_USE_QUERY, params![channel, name, caller], |row| {
+                row.get(0)
+            })?;
 
-    let result = connection.query_row(IS_TRUSTED_QUERY, rusqlite::params![username], |row| {
-        row.get::<usize, i32>(0)
-    });
+        Ok(count)
+    }
 
-    result.is_ok()
+    fn get_usage(&self, name: &str, channel: &str) -> Result<Option<CommandUsage>, StoreError> {
+        let connection = self.open()?;
+
+        const GET_USAGE_QUERY: &str = "
+        	SELECT count, last_caller, last_used_at
+        	FROM command_usage
+        	WHERE channel = ?1
+        	AND name = ?2
+        ";
+
+        let usage = connection
+            .query_row(GET_USAGE_QUERY, params![channel, name], |row| {
+                Ok(CommandUsage {
+                    count: row.get("count")?,
+                    last_caller: row.get("last_caller")?,
+                    last_used_at: row.get("last_used_at")?,
+                })
+            })
+            .optional()?;
+
+        Ok(usage)
+    }
 }
 
-pub fn trust_user(username: &str) {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+impl TrustStore for SqliteStore {
+    fn is_trusted(&self, username: &str, channel: &str) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const IS_TRUSTED_QUERY: &str = "
+        	SELECT 1
+        	FROM trusted_users
+        	WHERE username = ?1
+        	AND channel = ?2
+        	AND deleted_at IS NULL
+        	LIMIT 1
+    	";
+
+        let mut statement = connection.prepare(IS_TRUSTED_QUERY)?;
+        Ok(statement.exists([username, channel])?)
+    }
+
+    fn trust_user(&self, username: &str, channel: &str) -> Result<(), StoreError> {
+        let connection = self.open()?;
+
+        const TRUST_USER_QUERY: &str = "
+            INSERT INTO trusted_users (username, channel, created_at, updated_at)
+            VALUES (?1, ?2, datetime('now'), datetime('now'))
+            ON CONFLICT (username, IFNULL(channel, '')) DO UPDATE SET
+                deleted_at = NULL,
+                updated_at = datetime('now')
+            WHERE deleted_at IS NOT NULL
+        ";
 
-    if is_trusted(username) {
-        return;
+        connection.execute(TRUST_USER_QUERY, [username, channel])?;
+        Ok(())
     }
 
-    const TRUST_USER_QUERY: &str = "
-        INSERT INTO trusted_users (username, created_at, updated_at)
-        VALUES (?, datetime('now'), datetime('now'))
-        ON CONFLICT(username) DO UPDATE SET
-            deleted_at = NULL,
-            updated_at = datetime('now')
+    fn untrust_user(&self, username: &str, channel: &str) -> Result<bool, StoreError> {
+        let connection = self.open()?;
+
+        const UNTRUST_USER_QUERY: &str = "
+        	UPDATE trusted_users
+        	SET deleted_at = datetime('now'),
+    			updated_at = datetime('now')
+        	WHERE username = ?1
+        	AND channel = ?2
+        	AND deleted_at IS NULL
+        ";
+
+        let untrusted = connection.execute(UNTRUST_USER_QUERY, [username, channel])?;
+        Ok(untrusted > 0)
+    }
+}
+
+fn live_command_id(conn: &Connection, name: &str, channel: Option<&str>) -> Result<Option<i64>> {
+    const LIVE_COMMAND_ID_QUERY: &str = "
+    	SELECT id
+    	FROM commands
+    	WHERE name = ?1
+    	AND channel IS ?2
+    	AND deleted_at IS NULL
+    ";
+
+    conn.query_row(LIVE_COMMAND_ID_QUERY, params![name, channel], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+fn extra_responses(conn: &Connection, command_id: i64) -> Result<Vec<WeightedResponse>> {
+    const EXTRA_RESPONSES_QUERY: &str = "
+    	SELECT response, weight
+    	FROM command_responses
+    	WHERE command_id = ?1
+    	ORDER BY id
     ";
 
-    connection.execute(TRUST_USER_QUERY, [&username]).unwrap();
+    let mut statement = conn.prepare(EXTRA_RESPONSES_QUERY)?;
+    let responses = statement.query_map([command_id], |row| {
+        Ok(WeightedResponse {
+            text: row.get("response")?,
+            weight: row.get("weight")?,
+        })
+    })?;
+
+    responses.collect()
 }
 
-pub fn untrust_user(username: &str) {
-    let connection = Connection::open(DATABASE_PATH).unwrap();
+/// Returns the response of the live command `name` in exactly `channel`.
+fn live_response(conn: &Connection, name: &str, channel: Option<&str>) -> Result<Option<String>> {
+    const LIVE_RESPONSE_QUERY: &str = "
+    	SELECT response
+    	FROM commands
+    	WHERE name = ?1
+    	AND channel IS ?2
+    	AND deleted_at IS NULL
+    ";
 
-    const UNTRUST_USER_QUERY: &str = "
-    	UPDATE trusted_users
-    	SET deleted_at = datetime('now'),
-			updated_at = datetime('now')
-    	WHERE username = ?
+    conn.query_row(LIVE_RESPONSE_QUERY, params![name, channel], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+fn record_revision(
+    conn: &Connection,
+    name: &str,
+    channel: Option<&str>,
+    action: &str,
+    old_response: Option<&str>,
+    new_response: Option<&str>,
+    author: &str,
+) -> Result<()> {
+    const RECORD_REVISION_QUERY: &str = "
+    	INSERT INTO command_revisions (name, channel, action, old_response, new_response, author, created_at)
+    	VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))
     ";
 
-    connection.execute(UNTRUST_USER_QUERY, [&username]).unwrap();
+    conn.execute(
+        RECORD_REVISION_QUERY,
+        params![name, channel, action, old_response, new_response, author],
+    )?;
+    Ok(())
 }
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use crate::commands::cooldown::Cooldown;
+use crate::commands::mode::{ResponseMode, WeightedResponse};
+use crate::commands::role::Role;
+
+use super::error::StoreError;
+
+#[derive(Debug, Clone)]
+pub struct CustomCommand {
+    pub id: i64,
+    /// The main response, which `addcmd` and `updcmd` write, followed by any
+    /// added with `cmd addresp`.
+    pub responses: Vec<WeightedResponse>,
+    pub mode: ResponseMode,
+    pub role: Role,
+    pub cooldown: Cooldown,
+    pub description: Option<String>,
+    pub created_at: String,
+}
+
+/// A single change made to a custom command.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub number: u32,
+    pub action: String,
+    pub old_response: Option<String>,
+    pub new_response: Option<String>,
+    pub author: String,
+    pub created_at: String,
+}
+
+/// How often a command was used in a channel, and by whom last.
+#[derive(Debug, Clone)]
+pub struct CommandUsage {
+    pub count: u64,
+    pub last_used_at: String,
+    pub last_caller: String,
+}
+
+// Custom commands are scoped to a channel. Commands in the global scope are
+// available in every channel that does not define a command with the same
+// name. Methods that write take the exact scope to change, where `None` means
+// the global scope.
+pub trait CommandStore: Send + Sync {
+    /// Adds `name`, replacing a deleted command with the same name. Returns
+    /// whether it was added; a live command with that name is left alone.
+    fn create_command(
+        &self,
+        name: &str,
+        response: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError>;
+
+    /// Returns the name and required role of every command available in
+    /// `channel`, including global commands that the channel does not override.
+    fn get_commands(&self, channel: &str) -> Result<Vec<(String, Role)>, StoreError>;
+
+    /// Returns the command `name` in `channel`, falling back to the global
+    /// command when the channel does not define its own.
+    fn get_command(&self, name: &str, channel: &str) -> Result<Option<CustomCommand>, StoreError>;
+
+    fn command_exists(&self, name: &str, channel: Option<&str>) -> Result<bool, StoreError>;
+
+    fn update_command_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError>;
+
+    /// Puts back a response from an earlier revision, recording the revert as
+    /// a revision of its own.
+    fn revert_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+        author: &str,
+    ) -> Result<bool, StoreError>;
+
+    /// Soft-deletes the live command `name`. Returns whether there was one.
+    fn delete_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError>;
+
+    /// Brings back the soft-deleted command `name` as it was when it was
+    /// deleted. Returns whether there was one to restore.
+    fn restore_command(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        author: &str,
+    ) -> Result<bool, StoreError>;
+
+    /// Returns the most recent revisions of `name`, newest first. Revisions
+    /// are numbered from 1 in the order they were made.
+    fn get_revisions(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Revision>, StoreError>;
+
+    fn get_revision(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: u32,
+    ) -> Result<Option<Revision>, StoreError>;
+
+    /// Sets the minimum role needed to run `name`. Returns whether the command
+    /// exists in that scope.
+    fn set_command_role(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        role: Role,
+    ) -> Result<bool, StoreError>;
+
+    /// Sets the description `help` shows for `name`, or clears it. Returns
+    /// whether the command exists in that scope.
+    fn set_command_description(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<bool, StoreError>;
+
+    /// Sets how long `name` stays on cooldown after it runs. Returns whether
+    /// the command exists in that scope.
+    fn set_command_cooldown(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        cooldown: Cooldown,
+    ) -> Result<bool, StoreError>;
+
+    /// Adds another response to `name`. Returns its number, or `None` when
+    /// the command does not exist in that scope.
+    fn add_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        response: &str,
+    ) -> Result<Option<usize>, StoreError>;
+
+    /// Removes response `number` of `name`. The main response, number 1, can
+    /// only be changed with `update_command_response`. Returns whether a
+    /// response was removed.
+    fn delete_response(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+    ) -> Result<bool, StoreError>;
+
+    /// Returns every response of `name` in order, the main one first.
+    fn list_responses(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+    ) -> Result<Option<(ResponseMode, Vec<WeightedResponse>)>, StoreError>;
+
+    /// Sets how `name` picks between its responses. Returns whether the
+    /// command exists in that scope.
+    fn set_response_mode(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        mode: ResponseMode,
+    ) -> Result<bool, StoreError>;
+
+    /// Sets the weight of response `number` of `name`. Returns whether that
+    /// response exists.
+    fn set_response_weight(
+        &self,
+        name: &str,
+        channel: Option<&str>,
+        number: usize,
+        weight: u32,
+    ) -> Result<bool, StoreError>;
+
+    /// Moves a rotating command on to its next response, returning the turn
+    /// it was on.
+    fn next_turn(&self, id: i64) -> Result<u64, StoreError>;
+}
+
+pub trait AliasStore: Send + Sync {
+    /// Returns the command `alias` points to in `channel`, falling back to
+    /// the global alias when the channel does not define its own.
+    fn resolve_alias(&self, alias: &str, channel: &str) -> Result<Option<String>, StoreError>;
+
+    /// Returns the command `alias` points to in exactly the given scope.
+    fn get_alias(&self, alias: &str, channel: Option<&str>) -> Result<Option<String>, StoreError>;
+
+    /// Points `alias` at `target` in the given scope, replacing any alias
+    /// with the same name.
+    fn create_alias(
+        &self,
+        alias: &str,
+        target: &str,
+        channel: Option<&str>,
+    ) -> Result<(), StoreError>;
+
+    /// Removes `alias` from the given scope. Returns whether it existed.
+    fn delete_alias(&self, alias: &str, channel: Option<&str>) -> Result<bool, StoreError>;
+}
+
+pub trait UsageStore: Send + Sync {
+    /// Counts a use of `name` in `channel` by `caller` and returns the new
+    /// total. Builtin and custom commands share the same counters.
+    fn record_use(&self, name: &str, channel: &str, caller: &str) -> Result<u64, StoreError>;
+
+    fn get_usage(&self, name: &str, channel: &str) -> Result<Option<CommandUsage>, StoreError>;
+}
+
+/// Users trusted with `!trust`. Trust only applies in the channel it was
+/// granted in; the config's trusted lists are never stored.
+pub trait TrustStore: Send + Sync {
+    fn is_trusted(&self, username: &str, channel: &str) -> Result<bool, StoreError>;
+
+    /// Trusts `username` in `channel`, which does nothing if they already are.
+    fn trust_user(&self, username: &str, channel: &str) -> Result<(), StoreError>;
+
+    /// Takes back trust granted with `trust_user`. Returns whether `username`
+    /// was trusted in `channel`.
+    fn untrust_user(&self, username: &str, channel: &str) -> Result<bool, StoreError>;
+}
+
+/// Everything the bot keeps between runs. Implemented by every backend that
+/// implements the individual stores.
+pub trait Storage: CommandStore + AliasStore + UsageStore + TrustStore {}
+
+impl<T: CommandStore + AliasStore + UsageStore + TrustStore> Storage for T {}
+
+/// Handle to the bot's storage, shared by the dispatcher and commands.
+/// Cloning it is cheap: every clone talks to the same backend.
+#[derive(Clone)]
+pub struct Store {
+    backend: Arc<dyn Storage>,
+}
+
+impl Store {
+    pub fn new(backend: impl Storage + 'static) -> Store {
+        Store {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Runs `f` on the blocking thread pool, so queries never stall the
+    /// async workers that handle chat.
+    pub async fn run<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&dyn Storage) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let backend = self.backend.clone();
+        match tokio::task::spawn_blocking(move || f(backend.as_ref())).await {
+            Ok(result) => result,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod database;
 pub mod scryfall;
+pub mod transport;
 pub mod twitch;
@@ -1,10 +1,10 @@
 use scryfall::card::Card;
 
+use crate::helpers::MAX_TWITCH_CHAT_MESSAGE_LENGTH;
+
 pub async fn get_card(card: String) -> Option<String> {
     match Card::named_fuzzy(&card).await {
         Ok(card) => {
-            const MAX_TWITCH_CHAT_MESSAGE_LENGTH: usize = 500;
-
             let mut formatted = String::new();
             formatted.push_str(&card.name);
 
@@ -38,7 +38,7 @@ pub async fn get_card(card: String) -> Option<String> {
                 formatted.push_str(&format!(" | {loyalty}"));
             }
 
-            match formatted.len() > MAX_TWITCH_CHAT_MESSAGE_LENGTH {
+            match formatted.chars().count() > MAX_TWITCH_CHAT_MESSAGE_LENGTH {
                 true => {
                     let mut url = card.scryfall_uri;
                     let query: Vec<(String, String)> = url
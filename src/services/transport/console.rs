@@ -0,0 +1,139 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use twitch_irc::message::{IRCMessage, ServerMessage, TwitchUserBasics};
+
+use super::{ChatTransport, Incoming};
+use crate::config::Config;
+
+const USAGE: &str = "Type chat lines as `[#<channel>] <user>[badges]: <message>`, e.g. `#chan alice[moderator,vip]: !ping`.";
+
+static LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:#(\w+)\s+)?([A-Za-z0-9_]{1,25})(?:\[([a-z_,]*)\])?:\s?(.*)$").unwrap()
+});
+static MESSAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// An offline backend that reads chat lines from stdin and prints replies to
+/// stdout, so commands can be tried without a Twitch connection.
+pub struct ConsoleTransport {
+    user: String,
+}
+
+impl ConsoleTransport {
+    pub fn connect(config: &Config) -> (Incoming, ConsoleTransport) {
+        let (sender, incoming) = mpsc::unbounded_channel();
+        let default_channel = config
+            .channels
+            .first()
+            .cloned()
+            .unwrap_or_else(|| String::from("console"));
+
+        println!("{USAGE} Messages go to #{default_channel} by default.");
+        std::thread::spawn(move || read_stdin(sender, default_channel));
+
+        let user = config
+            .user
+            .clone()
+            .unwrap_or_else(|| String::from("rustedbot"));
+        (incoming, ConsoleTransport { user })
+    }
+}
+
+#[async_trait]
+impl ChatTransport for ConsoleTransport {
+    async fn say(&self, channel: &str, text: &str) -> anyhow::Result<()> {
+        println!("#{channel} {}: {text}", self.user);
+        Ok(())
+    }
+
+    async fn reply(&self, channel: &str, message_id: &str, text: &str) -> anyhow::Result<()> {
+        println!("#{channel} {} (reply to {message_id}): {text}", self.user);
+        Ok(())
+    }
+
+    async fn whisper(&self, user: &TwitchUserBasics, text: &str) -> anyhow::Result<()> {
+        println!("{} whispers to {}: {text}", self.user, user.login);
+        Ok(())
+    }
+}
+
+fn read_stdin(sender: UnboundedSender<ServerMessage>, default_channel: String) {
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Failed to read from stdin: {e}");
+                break;
+            }
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let Some(captures) = LINE.captures(line.trim()) else {
+            println!("{USAGE}");
+            continue;
+        };
+
+        let channel = captures
+            .get(1)
+            .map_or(default_channel.as_str(), |c| c.as_str())
+            .to_ascii_lowercase();
+        let user = captures[2].to_ascii_lowercase();
+        let badges: Vec<&str> = captures
+            .get(3)
+            .map_or("", |b| b.as_str())
+            .split(',')
+            .filter(|b| !b.is_empty())
+            .collect();
+
+        match privmsg(&channel, &user, &badges, &captures[4]) {
+            Ok(message) => {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+            Err(e) => log::error!("Failed to build chat message from `{line}`: {e}"),
+        }
+    }
+}
+
+/// Builds the `ServerMessage` Twitch would deliver for `user` saying `text` in
+/// `channel` while wearing `badges`, so offline backends go through the same
+/// parsing as real chat.
+pub fn privmsg(
+    channel: &str,
+    user: &str,
+    badges: &[&str],
+    text: &str,
+) -> anyhow::Result<ServerMessage> {
+    let id = MESSAGE_ID.fetch_add(1, Ordering::Relaxed);
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let badges = badges
+        .iter()
+        .map(|badge| format!("{badge}/1"))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let raw = format!(
+        "@badge-info=;badges={badges};color=;display-name={user};emotes=;flags=;id=console-{id};mod=0;room-id={room_id};subscriber=0;tmi-sent-ts={timestamp};turbo=0;user-id={user_id};user-type= :{user}!{user}@{user}.tmi.twitch.tv PRIVMSG #{channel} :{text}",
+        room_id = fake_id(channel),
+        user_id = fake_id(user),
+    );
+
+    let message = IRCMessage::parse(&raw)?;
+    Ok(ServerMessage::try_from(message)?)
+}
+
+fn fake_id(login: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    login.hash(&mut hasher);
+    hasher.finish() as u32
+}
@@ -0,0 +1,76 @@
+pub mod console;
+pub mod outbound;
+pub mod twitch;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedReceiver;
+use twitch_irc::message::{ServerMessage, TwitchUserBasics};
+
+use crate::config::Config;
+
+pub type Incoming = UnboundedReceiver<ServerMessage>;
+
+/// A chat backend the bot receives messages from and sends replies through.
+///
+/// Incoming chat events are delivered as twitch-irc `ServerMessage`s on the
+/// receiver returned by `connect`, so every backend feeds the same dispatcher.
+#[async_trait]
+pub trait ChatTransport: Send + Sync {
+    async fn say(&self, channel: &str, text: &str) -> anyhow::Result<()>;
+
+    /// Sends `text` in `channel`, threaded as a reply to `message_id`.
+    async fn reply(&self, channel: &str, message_id: &str, text: &str) -> anyhow::Result<()>;
+
+    async fn whisper(&self, user: &TwitchUserBasics, text: &str) -> anyhow::Result<()>;
+
+    /// Sends `text` in `channel`, as a reply when `reply_to` is set. Backends
+    /// that queue messages may drop low-priority ones when they fall behind.
+    async fn send(
+        &self,
+        channel: &str,
+        reply_to: Option<&str>,
+        text: &str,
+        _priority: Priority,
+    ) -> anyhow::Result<()> {
+        match reply_to {
+            Some(message_id) => self.reply(channel, message_id, text).await,
+            None => self.say(channel, text).await,
+        }
+    }
+
+    /// Lets the backend track chat state, such as the bot's own badges and
+    /// the slow mode of each channel. Called for every incoming message.
+    fn observe(&self, _message: &ServerMessage) {}
+}
+
+/// How much an outgoing message matters. Replies to commands are `Normal`;
+/// unprompted chatter, such as subject replies, is `Low`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    Low,
+    Normal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Twitch,
+    Console,
+}
+
+pub fn connect(backend: Backend, config: &Config) -> (Incoming, Arc<dyn ChatTransport>) {
+    match backend {
+        Backend::Twitch => {
+            let (incoming, transport) = twitch::TwitchTransport::connect(config);
+            (
+                incoming,
+                Arc::new(outbound::Outbound::new(Arc::new(transport))),
+            )
+        }
+        Backend::Console => {
+            let (incoming, transport) = console::ConsoleTransport::connect(config);
+            (incoming, Arc::new(transport))
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Notify;
+use tokio::time::{self, Instant};
+use twitch_irc::message::{ServerMessage, TwitchUserBasics};
+
+use super::{ChatTransport, Priority};
+
+/// Twitch allows 20 messages per 30 seconds, or 100 in channels where the
+/// bot is a moderator. Going over gets the bot throttled everywhere.
+const WINDOW: Duration = Duration::from_secs(30);
+const LIMIT: usize = 20;
+const MODERATOR_LIMIT: usize = 100;
+
+/// Once this many messages are waiting, low-priority ones older than
+/// `STALE_AFTER` are dropped, then the oldest low-priority ones.
+const MAX_BACKLOG: usize = 10;
+const STALE_AFTER: Duration = Duration::from_secs(15);
+
+struct Queued {
+    channel: String,
+    reply_to: Option<String>,
+    text: String,
+    priority: Priority,
+    queued_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Room {
+    moderator: bool,
+    slow_mode: Duration,
+    last_sent: Option<Instant>,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Queued>,
+    sent: VecDeque<Instant>,
+    rooms: HashMap<String, Room>,
+}
+
+impl State {
+    fn push(&mut self, message: Queued) {
+        let duplicate = message.priority == Priority::Low
+            && self
+                .queue
+                .iter()
+                .any(|queued| queued.channel == message.channel && queued.text == message.text);
+
+        if duplicate {
+            log::debug!("Merged a repeated message for #{}", message.channel);
+            return;
+        }
+
+        self.queue.push_back(message);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if self.queue.len() <= MAX_BACKLOG {
+            return;
+        }
+
+        let now = Instant::now();
+        let before = self.queue.len();
+        self.queue.retain(|queued| {
+            queued.priority == Priority::Normal || now - queued.queued_at < STALE_AFTER
+        });
+
+        while self.queue.len() > MAX_BACKLOG {
+            match self
+                .queue
+                .iter()
+                .position(|queued| queued.priority == Priority::Low)
+            {
+                Some(index) => self.queue.remove(index),
+                None => break,
+            };
+        }
+
+        let dropped = before - self.queue.len();
+        if dropped > 0 {
+            log::warn!("Outbound backlog is full, dropped {dropped} low-priority messages");
+        }
+    }
+
+    /// Removes and returns the message that may go out first, if it may go
+    /// out now. Otherwise returns when the next one will be ready, so a room
+    /// in slow mode never holds up the others.
+    fn pop_ready(&mut self) -> Result<Queued, Option<Instant>> {
+        let now = Instant::now();
+
+        while matches!(self.sent.front(), Some(sent) if now - *sent >= WINDOW) {
+            self.sent.pop_front();
+        }
+
+        // Among messages ready at the same time, normal priority goes first,
+        // then the oldest.
+        let next = self
+            .queue
+            .iter()
+            .enumerate()
+            .map(|(index, queued)| {
+                (
+                    self.ready_at(queued, now),
+                    queued.priority != Priority::Normal,
+                    index,
+                )
+            })
+            .min();
+
+        match next {
+            Some((ready_at, _, index)) if ready_at <= now => {
+                let message = self
+                    .queue
+                    .remove(index)
+                    .expect("index comes from the queue");
+                self.record(&message.channel, now);
+                Ok(message)
+            }
+            Some((ready_at, _, _)) => Err(Some(ready_at)),
+            None => Err(None),
+        }
+    }
+
+    /// The moment `queued` may go out, given the rate limit and its room's
+    /// slow mode.
+    fn ready_at(&self, queued: &Queued, now: Instant) -> Instant {
+        let room = self.rooms.get(&queued.channel).copied().unwrap_or_default();
+
+        let limit = if room.moderator {
+            MODERATOR_LIMIT
+        } else {
+            LIMIT
+        };
+        let mut ready_at = now;
+
+        if self.sent.len() >= limit {
+            ready_at = ready_at.max(self.sent[self.sent.len() - limit] + WINDOW);
+        }
+
+        if let (false, Some(last_sent)) = (room.moderator, room.last_sent) {
+            ready_at = ready_at.max(last_sent + room.slow_mode);
+        }
+
+        ready_at
+    }
+
+    fn record(&mut self, channel: &str, at: Instant) {
+        self.sent.push_back(at);
+        self.rooms
+            .entry(String::from(channel))
+            .or_default()
+            .last_sent = Some(at);
+    }
+}
+
+/// Paces everything the bot says so it stays within Twitch's rate limits and
+/// the slow mode of each channel. Whispers are not rate limited here.
+pub struct Outbound {
+    inner: Arc<dyn ChatTransport>,
+    state: Arc<Mutex<State>>,
+    pending: Arc<Notify>,
+}
+
+impl Outbound {
+    /// Wraps `inner` and starts the task that drains the queue. Must be called
+    /// from within a Tokio runtime.
+    pub fn new(inner: Arc<dyn ChatTransport>) -> Outbound {
+        let outbound = Outbound {
+            inner,
+            state: Arc::default(),
+            pending: Arc::default(),
+        };
+
+        tokio::spawn(drain(
+            outbound.inner.clone(),
+            outbound.state.clone(),
+            outbound.pending.clone(),
+        ));
+
+        outbound
+    }
+}
+
+async fn drain(inner: Arc<dyn ChatTransport>, state: Arc<Mutex<State>>, pending: Arc<Notify>) {
+    loop {
+        let next = state.lock().unwrap().pop_ready();
+        let message = match next {
+            Ok(message) => message,
+            Err(None) => {
+                pending.notified().await;
+                continue;
+            }
+            // A new message may be able to go out before the one we would
+            // wait for, so any arrival wakes the task up early.
+            Err(Some(ready_at)) => {
+                let _ = time::timeout_at(ready_at, pending.notified()).await;
+                continue;
+            }
+        };
+
+        let result = match &message.reply_to {
+            Some(message_id) => {
+                inner
+                    .reply(&message.channel, message_id, &message.text)
+                    .await
+            }
+            None => inner.say(&message.channel, &message.text).await,
+        };
+
+        if let Err(e) = result {
+            log::error!("Failed to send message to #{}: {e}", message.channel);
+        }
+    }
+}
+
+#[async_trait]
+impl ChatTransport for Outbound {
+    async fn say(&self, channel: &str, text: &str) -> anyhow::Result<()> {
+        self.send(channel, None, text, Priority::Normal).await
+    }
+
+    async fn reply(&self, channel: &str, message_id: &str, text: &str) -> anyhow::Result<()> {
+        self.send(channel, Some(message_id), text, Priority::Normal)
+            .await
+    }
+
+    async fn whisper(&self, user: &TwitchUserBasics, text: &str) -> anyhow::Result<()> {
+        self.inner.whisper(user, text).await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        reply_to: Option<&str>,
+        text: &str,
+        priority: Priority,
+    ) -> anyhow::Result<()> {
+        self.state.lock().unwrap().push(Queued {
+            channel: String::from(channel),
+            reply_to: reply_to.map(String::from),
+            text: String::from(text),
+            priority,
+            queued_at: Instant::now(),
+        });
+        self.pending.notify_one();
+
+        Ok(())
+    }
+
+    fn observe(&self, message: &ServerMessage) {
+        let mut state = self.state.lock().unwrap();
+
+        match message {
+            ServerMessage::UserState(user_state) => {
+                let moderator = user_state
+                    .badges
+                    .iter()
+                    .any(|badge| badge.name == "moderator" || badge.name == "broadcaster");
+                let room = state
+                    .rooms
+                    .entry(user_state.channel_login.clone())
+                    .or_default();
+
+                if room.moderator != moderator {
+                    log::info!(
+                        "Bot is {} in #{}",
+                        if moderator {
+                            "a moderator"
+                        } else {
+                            "no longer a moderator"
+                        },
+                        user_state.channel_login
+                    );
+                }
+                room.moderator = moderator;
+            }
+            ServerMessage::RoomState(room_state) => {
+                if let Some(slow_mode) = room_state.slow_mode {
+                    state
+                        .rooms
+                        .entry(room_state.channel_login.clone())
+                        .or_default()
+                        .slow_mode = slow_mode;
+                }
+            }
+            _ => (),
+        }
+    }
+}
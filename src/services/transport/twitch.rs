@@ -0,0 +1,131 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::sync::OnceCell;
+use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::message::TwitchUserBasics;
+use twitch_irc::ClientConfig;
+use twitch_irc::SecureTCPTransport;
+use twitch_irc::TwitchIRCClient;
+
+use super::{ChatTransport, Incoming};
+use crate::config::Config;
+
+type Tcp = SecureTCPTransport;
+type Credentials = StaticLoginCredentials;
+type Client = TwitchIRCClient<Tcp, Credentials>;
+
+const VALIDATE_URL: &str = "https://id.twitch.tv/oauth2/validate";
+const WHISPERS_URL: &str = "https://api.twitch.tv/helix/whispers";
+
+pub struct TwitchTransport {
+    client: Client,
+    http: reqwest::Client,
+    token: Option<String>,
+    identity: OnceCell<Identity>,
+}
+
+/// The bot account behind `twitch.token`, needed for Helix API calls.
+struct Identity {
+    client_id: String,
+    user_id: String,
+}
+
+impl TwitchTransport {
+    pub fn connect(config: &Config) -> (Incoming, TwitchTransport) {
+        let client_config = get_config(config.user.clone(), config.token.clone());
+        let (incoming_messages, client) = Client::new(client_config);
+
+        for channel in &config.channels {
+            client.join(channel.clone()).unwrap();
+        }
+
+        let transport = TwitchTransport {
+            client,
+            http: reqwest::Client::new(),
+            token: config.token.clone(),
+            identity: OnceCell::new(),
+        };
+
+        (incoming_messages, transport)
+    }
+
+    async fn identity(&self, token: &str) -> anyhow::Result<&Identity> {
+        self.identity
+            .get_or_try_init(|| validate_token(&self.http, token))
+            .await
+    }
+}
+
+#[async_trait]
+impl ChatTransport for TwitchTransport {
+    async fn say(&self, channel: &str, text: &str) -> anyhow::Result<()> {
+        self.client
+            .say(String::from(channel), String::from(text))
+            .await?;
+        Ok(())
+    }
+
+    async fn reply(&self, channel: &str, message_id: &str, text: &str) -> anyhow::Result<()> {
+        self.client
+            .say_in_reply_to(&(channel, message_id), String::from(text))
+            .await?;
+        Ok(())
+    }
+
+    // Twitch no longer accepts `/w` over IRC, so whispers go through Helix.
+    async fn whisper(&self, user: &TwitchUserBasics, text: &str) -> anyhow::Result<()> {
+        let token = self
+            .token
+            .as_deref()
+            .context("Whispers require `twitch.token` to be set")?;
+        let identity = self.identity(token).await?;
+
+        self.http
+            .post(WHISPERS_URL)
+            .query(&[
+                ("from_user_id", identity.user_id.as_str()),
+                ("to_user_id", user.id.as_str()),
+            ])
+            .bearer_auth(token)
+            .header("Client-Id", &identity.client_id)
+            .json(&serde_json::json!({ "message": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+async fn validate_token(http: &reqwest::Client, token: &str) -> anyhow::Result<Identity> {
+    let body: serde_json::Value = http
+        .get(VALIDATE_URL)
+        .header("Authorization", format!("OAuth {token}"))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let field = |name: &str| {
+        body[name]
+            .as_str()
+            .map(String::from)
+            .with_context(|| format!("Token validation response has no `{name}`"))
+    };
+
+    Ok(Identity {
+        client_id: field("client_id")?,
+        user_id: field("user_id")?,
+    })
+}
+
+fn get_config(user: Option<String>, token: Option<String>) -> ClientConfig<Credentials> {
+    match (user, token) {
+        (Some(user), Some(token)) => {
+            let credentials = StaticLoginCredentials::new(user, Some(token));
+            ClientConfig::new_simple(credentials)
+        }
+        _ => ClientConfig::default(),
+    }
+}
@@ -1,65 +1,70 @@
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 
-use twitch_irc::login::StaticLoginCredentials;
 use twitch_irc::message::{PrivmsgMessage, ServerMessage};
-use twitch_irc::ClientConfig;
-use twitch_irc::SecureTCPTransport;
-use twitch_irc::TwitchIRCClient;
 
+use crate::commands::context::Services;
+use crate::commands::mode::ResponseMode;
 use crate::commands::registry::Registry;
-use crate::commands::Command;
-use crate::database;
-use crate::database::sqlite;
+use crate::commands::template::{self, Segment, Variables};
+use crate::commands::{Command, CommandContext, Cooldown, Response, Role};
+use crate::config::Config;
+use crate::helpers::{edit_distance, split_message};
 use crate::messages::Message;
+use crate::services::database::error::StoreError;
+use crate::services::transport::{self, Backend, ChatTransport, Incoming, Priority};
 
-type Tcp = SecureTCPTransport;
-type Credentials = StaticLoginCredentials;
-type Client = TwitchIRCClient<Tcp, Credentials>;
-type TokioMessage = tokio::sync::mpsc::UnboundedReceiver<ServerMessage>;
+pub type Client = Arc<dyn ChatTransport>;
 
-#[tokio::main]
-pub async fn init(user: Option<String>, token: Option<String>, channel: String, prefix: String) {
-    let config = get_config(user, token);
-    let (incoming_messages, client) = Client::new(config);
+type BoxFuture<'a> = Pin<Box<dyn Future<Output = Response> + Send + 'a>>;
+
+/// How deep `{cmd:...}` macros may call into each other before the inner
+/// ones are skipped.
+const MAX_MACRO_DEPTH: usize = 3;
+
+/// How many `{cmd:...}` macros one chat message may run in total, so commands
+/// that each call several others cannot fan out within the depth limit.
+const MAX_MACRO_RUNS: usize = 10;
 
-    client.join(channel.clone()).unwrap();
+/// Suggestions share the cooldown tracker under a key no command can have.
+const SUGGESTION_KEY: &str = "did-you-mean";
+const SUGGESTION_COOLDOWN: Cooldown = Cooldown::from_secs(0, 60);
+
+#[tokio::main]
+pub async fn init(services: Services, backend: Backend) {
+    let (incoming_messages, client) = transport::connect(backend, &services.config);
 
     let handler_task = tokio::spawn(message_handler_loop(
         incoming_messages,
         client,
-        channel,
-        prefix,
+        Arc::new(services),
     ));
 
     handler_task.await.unwrap();
 }
 
-async fn message_handler_loop(
-    mut messages: TokioMessage,
-    client: Client,
-    channel: String,
-    prefix: String,
-) {
+async fn message_handler_loop(mut messages: Incoming, client: Client, services: Arc<Services>) {
     while let Some(message) = messages.recv().await {
         let client_clone = client.clone();
-        let channel_clone = channel.clone();
-        let prefix_clone = prefix.clone();
+        let services_clone = services.clone();
 
         tokio::spawn(async move {
-            handle_server_message(message, client_clone, channel_clone, prefix_clone).await;
+            handle_server_message(message, client_clone, services_clone).await;
         });
     }
 }
 
-async fn handle_server_message(
+pub async fn handle_server_message(
     message: ServerMessage,
     client: Client,
-    channel: String,
-    prefix: String,
+    services: Arc<Services>,
 ) {
+    client.observe(&message);
+
     match message {
         ServerMessage::Privmsg(privmsg) => {
-            handle_private_message(privmsg, client, channel, prefix).await;
+            handle_private_message(privmsg, client, services).await;
         }
         ServerMessage::Notice(notice) => {
             log::info!("NOTICE: {}", notice.message_text);
@@ -82,98 +87,398 @@ async fn handle_server_message(
     }
 }
 
-async fn handle_private_message(
-    privmsg: PrivmsgMessage,
-    client: Client,
-    channel: String,
-    prefix: String,
-) {
-    let sender = &privmsg.sender.login;
-    let message_text = &privmsg.message_text;
+async fn handle_private_message(privmsg: PrivmsgMessage, client: Client, services: Arc<Services>) {
+    let prefix = services.config.prefix_for(&privmsg.channel_login);
 
-    if message_text.starts_with(&prefix) {
-        handle_command(message_text, sender, client, channel, &prefix).await;
+    if privmsg.message_text.starts_with(prefix) {
+        handle_command(privmsg, client, services).await;
     } else {
-        let message = Message::make(message_text, sender);
+        let message = Message::make(&privmsg.message_text, &privmsg.sender.login);
         if message.has_subject() {
-            handle_subject_message(message, sender, client, channel).await;
+            handle_subject_message(message, client, &privmsg, &services).await;
         }
     }
 }
 
-async fn handle_command(
-    message_text: &str,
-    sender: &str,
-    client: Client,
-    channel: String,
-    prefix: &str,
-) {
-    let mut args = message_text.strip_prefix(prefix).unwrap().split(' ');
-    let command_name = args.next().unwrap_or("");
+async fn handle_command(privmsg: PrivmsgMessage, client: Client, services: Arc<Services>) {
+    let prefix = services.config.prefix_for(&privmsg.channel_login);
+    let mut args = privmsg
+        .message_text
+        .strip_prefix(prefix)
+        .unwrap()
+        .split(' ');
+    let command_name = args.next().unwrap_or("").to_string();
     let command_args = args.collect::<Vec<&str>>().join(" ");
 
-    if let Some(command) = Registry::get(command_name) {
-        handle_builtin_command(command, &command_args, sender, client, channel).await;
-    } else {
-        handle_custom_command(command_name, sender, client, channel).await;
-    }
+    let ctx = CommandContext::new(command_args, privmsg, services).await;
+    let response = run_command(&command_name, &ctx, 0).await;
+
+    respond(
+        client,
+        &ctx.message,
+        response,
+        ctx.config(),
+        Priority::Normal,
+    )
+    .await;
 }
 
-async fn handle_subject_message(message: Message, sender: &str, client: Client, channel: String) {
+async fn handle_subject_message(
+    message: Message,
+    client: Client,
+    privmsg: &PrivmsgMessage,
+    services: &Services,
+) {
+    let sender = &message.sender;
+    let channel = &privmsg.channel_login;
     let subject = message.subject.clone().unwrap();
     let content = message.content.clone();
-    let response = message.get_response().await;
+    let response = message.get_response(&mut services.rng()).await;
 
-    log::debug!("@{sender} triggered the subject `{subject}` with message `{content}`. Response: {response}");
-    say(client, channel, response).await;
+    log::debug!("@{sender} triggered the subject `{subject}` with message `{content}` in #{channel}. Response: {response}");
+    respond(client, privmsg, response, &services.config, Priority::Low).await;
+}
+
+/// Runs the builtin or custom command `command_name`, resolving aliases
+/// first. `depth` counts how many macros deep the call is.
+fn run_command<'a>(command_name: &'a str, ctx: &'a CommandContext, depth: usize) -> BoxFuture<'a> {
+    Box::pin(async move {
+        if let Some(command) = Registry::get(command_name) {
+            handle_builtin_command(command, ctx).await
+        } else {
+            let name = String::from(command_name);
+            let channel = ctx.channel().to_owned();
+            let resolved = ctx
+                .store()
+                .run(move |store| store.resolve_alias(&name, &channel))
+                .await
+                .unwrap_or_else(|e| {
+                    log_store_error("resolve the alias", command_name, ctx, &e);
+                    None
+                });
+            let command_name = resolved.unwrap_or_else(|| String::from(command_name));
+            handle_custom_command(&command_name, ctx, depth).await
+        }
+    })
 }
 
 async fn handle_builtin_command(
     command: Arc<dyn Command + Send + Sync>,
-    args: &str,
-    sender: &str,
-    client: Client,
-    channel: String,
-) {
+    ctx: &CommandContext,
+) -> Response {
     let command_name = command.name();
-    if command.requires_trust() && !sqlite::is_trusted(sender) {
-        log::warn!("User {sender} tried to run the `{command_name}` command without permission. Consider adding them to the trusted users list.");
-        let response = format!("@{sender} you are not authorized to run this command.");
-        say(client, channel, response).await;
-    } else {
-        let response = command.execute(args, sender).await;
-        log::debug!("@{sender} triggered builtin command `{command_name}` with args `{args}`. Response: {response}");
-        say(client, channel, response).await;
+    let sender = ctx.sender();
+    let channel = ctx.channel();
+    let args = &ctx.args;
+
+    if let Some(denial) = check_role(ctx, command_name, command.required_role()) {
+        return denial;
+    }
+
+    if let Some(notice) = check_cooldown(ctx, command_name, command.cooldown()) {
+        return notice;
     }
+
+    let (owned_channel, owned_sender) = (channel.to_owned(), sender.to_owned());
+    let recorded = ctx
+        .store()
+        .run(move |store| store.record_use(command_name, &owned_channel, &owned_sender))
+        .await;
+    if let Err(e) = recorded {
+        log_store_error("record a use", command_name, ctx, &e);
+    }
+
+    let response = command.execute(ctx).await;
+    log::debug!("@{sender} triggered builtin command `{command_name}` in #{channel} with args `{args}`. Response: {response}");
+    response
 }
 
-async fn handle_custom_command(command_name: &str, sender: &str, client: Client, channel: String) {
-    match database::sqlite::get_command_response(command_name) {
-        Ok(response) => {
+async fn handle_custom_command(command_name: &str, ctx: &CommandContext, depth: usize) -> Response {
+    let sender = ctx.sender();
+    let channel = ctx.channel();
+    let args = &ctx.args;
+
+    let (owned_name, owned_channel) = (command_name.to_owned(), channel.to_owned());
+    let command = ctx
+        .store()
+        .run(move |store| store.get_command(&owned_name, &owned_channel))
+        .await;
+
+    match command {
+        Ok(Some(command)) => {
+            if let Some(denial) = check_role(ctx, command_name, command.role) {
+                return denial;
+            }
+
+            if let Some(notice) = check_cooldown(ctx, command_name, command.cooldown) {
+                return notice;
+            }
+
+            let id = command.id;
+            let turn = match (command.mode, command.responses.len()) {
+                (ResponseMode::Rotate, 2..) => ctx
+                    .store()
+                    .run(move |store| store.next_turn(id))
+                    .await
+                    .unwrap_or_else(|e| {
+                        log_store_error("rotate the response", command_name, ctx, &e);
+                        0
+                    }),
+                _ => 0,
+            };
+            let (owned_name, owned_channel, owned_sender) = (
+                command_name.to_owned(),
+                channel.to_owned(),
+                sender.to_owned(),
+            );
+            let count = ctx
+                .store()
+                .run(move |store| store.record_use(&owned_name, &owned_channel, &owned_sender))
+                .await
+                .unwrap_or_else(|e| {
+                    log_store_error("record a use", command_name, ctx, &e);
+                    0
+                });
+            let mut rng = ctx.services.rng();
+            let template = command
+                .mode
+                .choose(&command.responses, turn, &mut rng)
+                .unwrap_or("");
+            let segments = template::segments(
+                template,
+                &Variables {
+                    sender,
+                    channel,
+                    args,
+                    count,
+                },
+                &mut rng,
+            );
+            let response = expand_macros(segments, ctx, depth).await;
             log::debug!(
-                "@{sender} triggered custom command `{command_name}`. Response: {response}"
+                "@{sender} triggered custom command `{command_name}` in #{channel} with args `{args}`. Response: {response}"
             );
-            say(client, channel, response).await;
+            response
+        }
+        Ok(None) => {
+            log::debug!("@{sender} triggered unknown command `{command_name}` in #{channel}.");
+            match depth {
+                0 => suggest_command(command_name, ctx).await,
+                _ => Response::None,
+            }
         }
         Err(e) => {
-            log::debug!("@{sender} triggered custom command `{command_name}`. There was an error.");
-            log::error!("Error fetching command `{command_name}`: {e}");
+            log_store_error("fetch the command", command_name, ctx, &e);
+            Response::None
         }
     }
 }
 
-async fn say(client: Client, channel: String, text: String) {
-    if let Err(e) = client.say(channel, text).await {
-        log::error!("Failed to send message: {e}");
+/// Logs a failed store call made while dispatching `command_name`, with who
+/// ran it and where.
+fn log_store_error(action: &str, command_name: &str, ctx: &CommandContext, error: &StoreError) {
+    log::error!(
+        "Could not {action} for `{command_name}` run by @{} in #{}: {error}",
+        ctx.sender(),
+        ctx.channel()
+    );
+}
+
+/// Points the sender at the command closest to the unknown `command_name`,
+/// when the channel opted in and there is one within a couple of typos.
+/// Each user gets at most one suggestion per `SUGGESTION_COOLDOWN`.
+async fn suggest_command(command_name: &str, ctx: &CommandContext) -> Response {
+    let channel = ctx.channel();
+    if command_name.is_empty() || !ctx.config().suggestions_for(channel) {
+        return Response::None;
+    }
+
+    let builtins = Registry::all()
+        .into_iter()
+        .filter(|command| command.required_role() <= ctx.role)
+        .flat_map(|command| {
+            let mut names = vec![command.name()];
+            names.extend(command.aliases());
+            names
+        })
+        .map(String::from);
+    let owned_channel = channel.to_owned();
+    let custom = ctx
+        .store()
+        .run(move |store| store.get_commands(&owned_channel))
+        .await
+        .unwrap_or_else(|e| {
+            log_store_error("list commands to suggest", command_name, ctx, &e);
+            Vec::new()
+        })
+        .into_iter()
+        .filter(|(_, role)| *role <= ctx.role)
+        .map(|(name, _)| name);
+
+    let max_distance = (command_name.chars().count() / 3).clamp(1, 2);
+    let suggestion = builtins
+        .chain(custom)
+        .map(|name| (edit_distance(command_name, &name), name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min();
+
+    let Some((_, suggestion)) = suggestion else {
+        return Response::None;
+    };
+
+    let sender = ctx.sender();
+    if ctx
+        .services
+        .cooldowns
+        .try_use(channel, SUGGESTION_KEY, sender, SUGGESTION_COOLDOWN)
+        .is_some()
+    {
+        return Response::None;
     }
+
+    let prefix = ctx.config().prefix_for(channel);
+    Response::Reply(format!("@{sender} did you mean {prefix}{suggestion}?"))
 }
 
-fn get_config(user: Option<String>, token: Option<String>) -> ClientConfig<Credentials> {
-    match (user, token) {
-        (Some(user), Some(token)) => {
-            let credentials = StaticLoginCredentials::new(user, Some(token));
-            ClientConfig::new_simple(credentials)
+/// Turns the segments of a custom response into a response, running each
+/// `{cmd:...}` macro as the caller. Macro output that returns a message is
+/// joined with the text around it; macros separated only by whitespace send
+/// separate messages.
+async fn expand_macros(segments: Vec<Segment>, ctx: &CommandContext, depth: usize) -> Response {
+    let mut parts: Vec<Response> = Vec::new();
+    let mut joinable = false;
+
+    for segment in segments {
+        let response = match segment {
+            Segment::Text(text) if text.trim().is_empty() => {
+                joinable = false;
+                continue;
+            }
+            Segment::Text(text) => Response::Message(text),
+            Segment::Command(invocation) if depth >= MAX_MACRO_DEPTH => {
+                log::warn!("Not running `{invocation}` in #{}: macros are nested more than {MAX_MACRO_DEPTH} deep", ctx.channel());
+                continue;
+            }
+            Segment::Command(invocation) if ctx.count_macro() > MAX_MACRO_RUNS => {
+                log::warn!("Not running `{invocation}` in #{}: the message already ran {MAX_MACRO_RUNS} macros", ctx.channel());
+                continue;
+            }
+            Segment::Command(invocation) => {
+                let (name, args) = invocation.split_once(' ').unwrap_or((&invocation, ""));
+                let inner = ctx.with_args(String::from(args.trim()));
+                run_command(name, &inner, depth + 1).await
+            }
+        };
+
+        for part in response.into_parts() {
+            match (parts.last_mut(), part) {
+                (Some(Response::Message(last)), Response::Message(text)) if joinable => {
+                    last.push_str(&text);
+                }
+                (_, part) => parts.push(part),
+            }
+            joinable = matches!(parts.last(), Some(Response::Message(_)));
+        }
+    }
+
+    let mut parts: Vec<Response> = parts
+        .into_iter()
+        .map(|part| match part {
+            Response::Message(text) => Response::from(String::from(text.trim())),
+            part => part,
+        })
+        .filter(|part| *part != Response::None)
+        .collect();
+
+    match parts.len() {
+        0 => Response::None,
+        1 => parts.remove(0),
+        _ => Response::Sequence(parts),
+    }
+}
+
+/// Returns the denial to send when the sender's role is below `required`.
+fn check_role(ctx: &CommandContext, command_name: &str, required: Role) -> Option<Response> {
+    if ctx.role >= required {
+        return None;
+    }
+
+    let sender = ctx.sender();
+    let channel = ctx.channel();
+    let role = ctx.role;
+
+    log::warn!("User {sender} ({role}) tried to run the `{command_name}` command in #{channel}, which requires the {required} role.");
+    Some(Response::Whisper(format!(
+        "@{sender} you need the {required} role to run this command."
+    )))
+}
+
+/// Returns what to send when `command_name` is still on cooldown for the
+/// sender, and records the use otherwise. Users at or above the configured
+/// bypass role are never held back and do not start a cooldown.
+fn check_cooldown(
+    ctx: &CommandContext,
+    command_name: &str,
+    cooldown: Cooldown,
+) -> Option<Response> {
+    let config = ctx.config();
+    if ctx.role >= config.cooldown_bypass {
+        return None;
+    }
+
+    let sender = ctx.sender();
+    let channel = ctx.channel();
+    let remaining = ctx
+        .services
+        .cooldowns
+        .try_use(channel, command_name, sender, cooldown)?;
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+
+    log::debug!("User {sender} tried to run the `{command_name}` command in #{channel}, which is on cooldown for {seconds}s.");
+    let notice = match &config.cooldown_message {
+        Some(message) => Response::Whisper(
+            message
+                .replace("{sender}", sender)
+                .replace("{command}", command_name)
+                .replace("{seconds}", &seconds.to_string()),
+        ),
+        None => Response::None,
+    };
+
+    Some(notice)
+}
+
+/// Sends every part of `response`, splitting text that is too long for a
+/// single chat message.
+async fn respond(
+    client: Client,
+    privmsg: &PrivmsgMessage,
+    response: Response,
+    config: &Config,
+    priority: Priority,
+) {
+    let channel = &privmsg.channel_login;
+    let message_id = privmsg.message_id.as_str();
+
+    for part in response.into_parts() {
+        let (reply_to, text) = match &part {
+            Response::Message(text) | Response::Whisper(text) => (None, text),
+            Response::Reply(text) => (Some(message_id), text),
+            Response::None | Response::Sequence(_) => continue,
+        };
+
+        for text in split_message(text, config.max_parts) {
+            let result = match &part {
+                // Whispers carry private notices, such as cooldowns and
+                // permission denials, so one that fails is dropped rather
+                // than posted in chat.
+                Response::Whisper(_) => client.whisper(&privmsg.sender, &text).await,
+                _ => client.send(channel, reply_to, &text, priority).await,
+            };
+
+            if let Err(e) = result {
+                log::error!("Failed to send message: {e}");
+            }
         }
-        _ => ClientConfig::default(),
     }
 }
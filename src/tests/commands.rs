@@ -0,0 +1,278 @@
+// Runs commands directly against the in-memory backend, without the
+// dispatcher or a database file.
+
+use std::sync::Arc;
+
+use twitch_irc::message::ServerMessage;
+
+use crate::commands::addcmd::AddCmd;
+use crate::commands::context::{CommandContext, Services};
+use crate::commands::delcmd::DelCmd;
+use crate::commands::trust::Trust;
+use crate::commands::untrust::Untrust;
+use crate::commands::updcmd::UpdateCmd;
+use crate::commands::{Command, Response};
+use crate::config::{self, Config};
+use crate::services::database::memory::MemoryStore;
+use crate::services::database::store::{Storage, Store};
+use crate::services::transport::console;
+
+use super::{CHANNEL, OWNER};
+
+fn services() -> Arc<Services> {
+    services_with(config::default())
+}
+
+fn services_with(config: Config) -> Arc<Services> {
+    let store = MemoryStore::default();
+    Arc::new(Services::new(Store::new(store), config))
+}
+
+/// Runs `command` with `args` as `OWNER` in `CHANNEL`.
+async fn execute(services: &Arc<Services>, command: impl Command, args: &str) -> Response {
+    let ServerMessage::Privmsg(message) = console::privmsg(CHANNEL, OWNER, &[], "").unwrap() else {
+        unreachable!("console::privmsg builds a PRIVMSG");
+    };
+    let ctx = CommandContext::new(String::from(args), message, services.clone()).await;
+
+    command.execute(&ctx).await
+}
+
+async fn query<T, F>(services: &Services, f: F) -> T
+where
+    F: FnOnce(&dyn Storage) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    services.store.run(f).await
+}
+
+fn message(text: &str) -> Response {
+    Response::Message(String::from(text))
+}
+
+async fn response_of(services: &Services, name: &'static str) -> Option<String> {
+    query(services, move |store| {
+        store
+            .get_command(name, CHANNEL)
+            .unwrap()
+            .map(|command| command.responses[0].text.clone())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn addcmd_creates_a_command_in_the_channel() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, AddCmd, "hello Hello, chat!").await,
+        message("Command added!")
+    );
+    assert_eq!(
+        response_of(&services, "hello").await.as_deref(),
+        Some("Hello, chat!")
+    );
+    assert!(
+        query(&services, |store| store
+            .command_exists("hello", Some(CHANNEL))
+            .unwrap())
+        .await
+    );
+}
+
+#[tokio::test]
+async fn addcmd_updates_an_existing_command() {
+    let services = services();
+
+    execute(&services, AddCmd, "hello Hello, chat!").await;
+
+    assert_eq!(
+        execute(&services, AddCmd, "hello Hi again").await,
+        message("Command already exists. It was updated with the new response.")
+    );
+    assert_eq!(
+        response_of(&services, "hello").await.as_deref(),
+        Some("Hi again")
+    );
+}
+
+#[tokio::test]
+async fn creating_a_live_command_changes_nothing() {
+    let services = services();
+
+    execute(&services, AddCmd, "hello Hello, chat!").await;
+
+    assert!(
+        !query(&services, |store| store
+            .create_command("hello", "Hi", Some(CHANNEL), OWNER)
+            .unwrap())
+        .await
+    );
+    assert_eq!(
+        response_of(&services, "hello").await.as_deref(),
+        Some("Hello, chat!")
+    );
+    let revisions = query(&services, |store| {
+        store.get_revisions("hello", Some(CHANNEL), 10).unwrap()
+    })
+    .await;
+    assert_eq!(revisions.len(), 1);
+}
+
+#[tokio::test]
+async fn addcmd_refuses_builtin_names() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, AddCmd, "ping Pong?").await,
+        message("The command you are trying to add already exists as a built-in command.")
+    );
+}
+
+#[tokio::test]
+async fn delcmd_deletes_a_command() {
+    let services = services();
+
+    execute(&services, AddCmd, "hello Hello, chat!").await;
+
+    assert_eq!(
+        execute(&services, DelCmd, "hello").await,
+        message("Command deleted!")
+    );
+    assert_eq!(response_of(&services, "hello").await, None);
+    assert_eq!(
+        execute(&services, DelCmd, "hello").await,
+        message("Command hello does not exist.")
+    );
+}
+
+#[tokio::test]
+async fn delcmd_keeps_the_deleted_response_for_undelcmd() {
+    let services = services();
+
+    execute(&services, AddCmd, "hello Hello, chat!").await;
+    execute(&services, DelCmd, "hello").await;
+
+    assert!(
+        query(&services, |store| store
+            .restore_command("hello", Some(CHANNEL), OWNER)
+            .unwrap())
+        .await
+    );
+    assert_eq!(
+        response_of(&services, "hello").await.as_deref(),
+        Some("Hello, chat!")
+    );
+}
+
+#[tokio::test]
+async fn updcmd_changes_the_response_and_records_a_revision() {
+    let services = services();
+
+    execute(&services, AddCmd, "hello Hello, chat!").await;
+
+    assert_eq!(
+        execute(&services, UpdateCmd, "hello Hi, chat!").await,
+        message("Command updated!")
+    );
+    assert_eq!(
+        response_of(&services, "hello").await.as_deref(),
+        Some("Hi, chat!")
+    );
+
+    let revisions = query(&services, |store| {
+        store.get_revisions("hello", Some(CHANNEL), 10).unwrap()
+    })
+    .await;
+    let actions: Vec<&str> = revisions.iter().map(|r| r.action.as_str()).collect();
+    assert_eq!(actions, vec!["update", "add"]);
+    assert_eq!(revisions[0].old_response.as_deref(), Some("Hello, chat!"));
+}
+
+#[tokio::test]
+async fn updcmd_needs_an_existing_command() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, UpdateCmd, "hello Hi, chat!").await,
+        message("Command hello does not exist. Use addcmd to create it.")
+    );
+    assert_eq!(response_of(&services, "hello").await, None);
+}
+
+#[tokio::test]
+async fn trust_and_untrust_change_who_is_trusted() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, Trust, "helper").await,
+        message("helper has been trusted.")
+    );
+    assert!(
+        query(&services, |store| store
+            .is_trusted("helper", CHANNEL)
+            .unwrap())
+        .await
+    );
+
+    assert_eq!(
+        execute(&services, Untrust, "helper").await,
+        message("helper has been untrusted.")
+    );
+    assert!(
+        !query(&services, |store| store
+            .is_trusted("helper", CHANNEL)
+            .unwrap())
+        .await
+    );
+}
+
+#[tokio::test]
+async fn untrust_says_when_nothing_changed() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, Untrust, "helper").await,
+        message("helper was not trusted.")
+    );
+
+    execute(&services, Trust, "helper").await;
+    execute(&services, Untrust, "helper").await;
+    assert_eq!(
+        execute(&services, Untrust, "helper").await,
+        message("helper was not trusted.")
+    );
+}
+
+#[tokio::test]
+async fn untrust_cannot_remove_config_trust() {
+    let mut config = config::default();
+    config.trusted_users = vec![String::from("helper")];
+    let services = services_with(config);
+
+    execute(&services, Trust, "helper").await;
+    assert_eq!(
+        execute(&services, Untrust, "helper").await,
+        message("helper is trusted by config and can't be untrusted here.")
+    );
+    assert!(
+        !query(&services, |store| store
+            .is_trusted("helper", CHANNEL)
+            .unwrap())
+        .await
+    );
+}
+
+#[tokio::test]
+async fn trust_rejects_invalid_usernames() {
+    let services = services();
+
+    assert_eq!(
+        execute(&services, Trust, "no").await,
+        message("Username must be a valid Twitch username.")
+    );
+    assert_eq!(
+        execute(&services, Untrust, "").await,
+        message("@owner USAGE: untrust <username>")
+    );
+}
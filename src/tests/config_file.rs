@@ -0,0 +1,44 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::config::Config;
+
+fn load(contents: &str) -> Config {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bot.conf");
+    fs::write(&path, contents).unwrap();
+
+    Config::from_file(path.to_str().unwrap())
+}
+
+#[test]
+fn values_keep_equals_signs_and_fragments() {
+    let config = load(
+        "\
+# Full line comment
+twitch.channels=Foo, bar # trailing comment
+command.prefix = \"?\"
+command.list_url=https://host/cmds?chan={channel}&page=1#list
+command.list_path=/srv/www/cmds/{channel}.html
+",
+    );
+
+    assert_eq!(config.channels, vec!["foo", "bar"]);
+    assert_eq!(config.prefix, "?");
+    assert_eq!(
+        config.list_url.as_deref(),
+        Some("https://host/cmds?chan={channel}&page=1#list")
+    );
+    assert_eq!(
+        config.list_url_for("foo").as_deref(),
+        Some("https://host/cmds?chan=foo&page=1#list")
+    );
+}
+
+#[test]
+fn a_hash_after_the_equals_sign_is_part_of_the_value() {
+    let config = load("command.prefix=#\n");
+
+    assert_eq!(config.prefix, "#");
+}
@@ -0,0 +1,955 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use super::{Harness, Sent, CHANNEL, OWNER};
+use crate::config;
+use crate::helpers::MAX_TWITCH_CHAT_MESSAGE_LENGTH;
+
+#[tokio::test]
+async fn builtin_command_replies_in_channel() {
+    let bot = Harness::new();
+
+    let replies = bot
+        .inject("@badge-info=;badges=;color=;display-name=Viewer;emotes=;flags=;id=abc-1;mod=0;room-id=1;subscriber=0;tmi-sent-ts=1594545155039;turbo=0;user-id=2;user-type= :viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #testchannel :!ping")
+        .await;
+
+    assert_eq!(
+        replies,
+        vec![Sent::Say {
+            channel: String::from(CHANNEL),
+            text: String::from("Pong!")
+        }]
+    );
+}
+
+#[tokio::test]
+async fn trusted_user_adds_custom_command() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send(OWNER, "!addcmd hello Hello, chat!").await,
+        vec!["Command added!"]
+    );
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hello, chat!"]);
+
+    assert_eq!(
+        bot.send(OWNER, "!addcmd hello Hi again").await,
+        vec!["Command already exists. It was updated with the new response."]
+    );
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hi again"]);
+}
+
+#[tokio::test]
+async fn untrusted_user_is_denied() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send("viewer", "!addcmd hello Hello, chat!").await,
+        vec!["@viewer you need the trusted role to run this command."]
+    );
+    assert!(bot.send("viewer", "!hello").await.is_empty());
+}
+
+#[tokio::test]
+async fn channel_trusted_list_replaces_the_global_one() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL), String::from("other")];
+    config.trusted_users = vec![String::from(OWNER)];
+    config.overrides.insert(
+        String::from("other"),
+        config::ChannelOverrides {
+            trusted_users: Some(vec![String::from("helper")]),
+            ..Default::default()
+        },
+    );
+    let bot = Harness::with_config(config);
+
+    assert_eq!(
+        bot.send_in("other", OWNER, "!addcmd hello Hello").await,
+        vec!["@owner you need the trusted role to run this command."]
+    );
+    assert_eq!(
+        bot.send_in("other", "helper", "!addcmd hello Hello").await,
+        vec!["Command added!"]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!addcmd hello Hello").await,
+        vec!["Command added!"]
+    );
+}
+
+#[tokio::test]
+async fn trust_and_untrust_change_permissions() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send(OWNER, "!trust helper").await,
+        vec!["helper has been trusted."]
+    );
+    assert_eq!(
+        bot.send("helper", "!addcmd rules Be nice").await,
+        vec!["Command added!"]
+    );
+
+    assert_eq!(
+        bot.send(OWNER, "!untrust helper").await,
+        vec!["helper has been untrusted."]
+    );
+    assert_eq!(
+        bot.send("helper", "!delcmd rules").await,
+        vec!["@helper you need the trusted role to run this command."]
+    );
+}
+
+#[tokio::test]
+async fn trust_only_applies_in_its_channel() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL), String::from("other")];
+    config.overrides.insert(
+        String::from("other"),
+        config::ChannelOverrides {
+            trusted_users: Some(vec![String::from("alice")]),
+            ..Default::default()
+        },
+    );
+    let bot = Harness::with_config(config);
+
+    assert_eq!(
+        bot.send_in("other", "alice", "!trust mallory").await,
+        vec!["mallory has been trusted."]
+    );
+    assert_eq!(
+        bot.send_in("other", "mallory", "!addcmd hello Hello").await,
+        vec!["Command added!"]
+    );
+    assert_eq!(
+        bot.send("mallory", "!addcmd hello Hello").await,
+        vec!["@mallory you need the trusted role to run this command."]
+    );
+
+    assert_eq!(
+        bot.send_in("other", "alice", "!untrust mallory").await,
+        vec!["mallory has been untrusted."]
+    );
+    assert_eq!(
+        bot.send_in("other", "alice", "!untrust mallory").await,
+        vec!["mallory was not trusted."]
+    );
+}
+
+#[tokio::test]
+async fn only_global_trust_can_change_global_commands() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL), String::from("other")];
+    config.trusted_users = vec![String::from(OWNER)];
+    config.overrides.insert(
+        String::from("other"),
+        config::ChannelOverrides {
+            trusted_users: Some(vec![String::from("alice"), String::from(OWNER)]),
+            ..Default::default()
+        },
+    );
+    let bot = Harness::with_config(config);
+    bot.send_in("other", "alice", "!trust mallory").await;
+
+    for user in ["alice", "mallory"] {
+        assert_eq!(
+            bot.send_in("other", user, "!addcmd --global hello Hello")
+                .await,
+            vec![format!(
+                "@{user} only the broadcaster or users trusted in every channel can use --global."
+            )]
+        );
+    }
+    assert!(bot.send("viewer", "!hello").await.is_empty());
+
+    assert_eq!(
+        bot.send_in("other", OWNER, "!addcmd --global hello Hello")
+            .await,
+        vec!["Command added!"]
+    );
+    assert_eq!(
+        bot.send_in("other", "other", "!updcmd --global hello Hi")
+            .await,
+        vec!["Command updated!"]
+    );
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hi"]);
+}
+
+#[tokio::test]
+async fn badges_grant_roles() {
+    let bot = Harness::new();
+
+    bot.send(OWNER, "!addcmd modsonly For mods").await;
+    assert_eq!(
+        bot.send(OWNER, "!cmd role modsonly moderator").await,
+        vec!["modsonly now requires the moderator role."]
+    );
+
+    assert_eq!(
+        bot.send_with_badges("viewer", &["subscriber"], "!modsonly")
+            .await,
+        vec!["@viewer you need the moderator role to run this command."]
+    );
+    assert_eq!(
+        bot.send_with_badges("helper", &["moderator"], "!modsonly")
+            .await,
+        vec!["For mods"]
+    );
+    assert_eq!(
+        bot.send_with_badges("helper", &["moderator"], "!addcmd nope Nope")
+            .await,
+        vec!["@helper you need the trusted role to run this command."]
+    );
+}
+
+#[tokio::test]
+async fn broadcaster_outranks_trusted() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send_with_badges(CHANNEL, &["broadcaster"], "!addcmd mine Mine")
+            .await,
+        vec!["Command added!"]
+    );
+}
+
+#[tokio::test]
+async fn custom_commands_are_scoped_to_channels() {
+    let mut config = config::default();
+    config.channels = vec![String::from("first"), String::from("second")];
+    config.trusted_users = vec![String::from(OWNER)];
+    let bot = Harness::with_config(config);
+
+    bot.send_in("first", OWNER, "!addcmd only First only").await;
+    bot.send_in("first", OWNER, "!addcmd --global everywhere Everywhere")
+        .await;
+
+    assert_eq!(
+        bot.send_in("first", "viewer", "!only").await,
+        vec!["First only"]
+    );
+    assert!(bot.send_in("second", "viewer", "!only").await.is_empty());
+    assert_eq!(
+        bot.send_in("second", "viewer", "!everywhere").await,
+        vec!["Everywhere"]
+    );
+}
+
+#[tokio::test]
+async fn ocaml_is_corrected() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send("viewer", "I love ocaml").await,
+        vec!["@viewer Não é ocaml, é OCaml."]
+    );
+}
+
+#[tokio::test]
+async fn ocaml_correction_is_a_threaded_reply() {
+    let bot = Harness::new();
+
+    let replies = bot.deliver("viewer", "Ocaml").await;
+
+    assert_eq!(
+        replies,
+        vec![Sent::Reply {
+            channel: String::from(CHANNEL),
+            text: String::from("@viewer Não é Ocaml, é OCaml.")
+        }]
+    );
+}
+
+#[tokio::test]
+async fn correctly_spelled_ocaml_sends_nothing() {
+    let bot = Harness::new();
+
+    assert!(bot.send("viewer", "I love OCaml").await.is_empty());
+}
+
+#[tokio::test]
+async fn permission_denial_is_whispered() {
+    let bot = Harness::new();
+
+    let replies = bot.deliver("viewer", "!trust viewer").await;
+
+    assert_eq!(
+        replies,
+        vec![Sent::Whisper {
+            user: String::from("viewer"),
+            text: String::from("@viewer you need the trusted role to run this command.")
+        }]
+    );
+}
+
+#[tokio::test]
+async fn failed_whispers_are_not_posted_in_chat() {
+    let bot = Harness::new();
+    bot.fail_whispers();
+
+    assert!(bot.deliver("viewer", "!trust viewer").await.is_empty());
+}
+
+#[tokio::test]
+async fn greetings_mention_the_sender() {
+    let bot = Harness::new();
+
+    let replies = bot.send("viewer", "oi bot").await;
+
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].contains("@viewer"));
+}
+
+#[tokio::test]
+async fn unknown_command_is_ignored() {
+    let bot = Harness::new();
+
+    assert!(bot.send("viewer", "!doesnotexist").await.is_empty());
+}
+
+#[tokio::test]
+async fn custom_command_cooldowns() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd hello Hello, chat!").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!cmd cooldown hello 60 120").await,
+        vec!["hello now has a 60s cooldown and a 120s cooldown per user."]
+    );
+
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hello, chat!"]);
+    assert!(bot.send("viewer", "!hello").await.is_empty());
+    assert!(bot.send("other", "!hello").await.is_empty());
+    assert_eq!(
+        bot.send_with_badges("modesto", &["moderator"], "!hello")
+            .await,
+        vec!["Hello, chat!"]
+    );
+}
+
+#[tokio::test]
+async fn cooldown_message_is_whispered() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.trusted_users = vec![String::from(OWNER)];
+    config.cooldown_message = Some(String::from("@{sender} {command} is on cooldown."));
+    let bot = Harness::with_config(config);
+
+    bot.send(OWNER, "!addcmd hello Hello, chat!").await;
+    bot.send(OWNER, "!cmd cooldown hello 0 60").await;
+
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hello, chat!"]);
+    assert_eq!(
+        bot.deliver("viewer", "!hello").await,
+        vec![Sent::Whisper {
+            user: String::from("viewer"),
+            text: String::from("@viewer hello is on cooldown.")
+        }]
+    );
+    assert_eq!(bot.send("other", "!hello").await, vec!["Hello, chat!"]);
+}
+
+#[tokio::test]
+async fn long_responses_are_split() {
+    let bot = Harness::new();
+    let response = "spam ".repeat(150);
+    bot.send(OWNER, &format!("!addcmd long {response}")).await;
+
+    let replies = bot.send("viewer", "!long").await;
+
+    assert_eq!(replies.len(), 2);
+    assert!(replies[0].starts_with("spam spam"));
+    assert!(replies[0].ends_with("(1/2)"));
+    assert!(replies[1].ends_with("(2/2)"));
+}
+
+#[tokio::test]
+async fn custom_responses_are_templated() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd hug {sender} hugs {touser} ({count})")
+        .await;
+
+    assert_eq!(
+        bot.send("viewer", "!hug @friend").await,
+        vec!["viewer hugs friend (1)"]
+    );
+    assert_eq!(
+        bot.send("viewer", "!hug").await,
+        vec!["viewer hugs viewer (2)"]
+    );
+}
+
+#[tokio::test]
+async fn usage_is_counted_per_channel() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd hello Hello #{count}").await;
+
+    assert_eq!(bot.send("viewer", "!hello").await, vec!["Hello #1"]);
+    assert_eq!(bot.send("other", "!hello").await, vec!["Hello #2"]);
+    bot.send("viewer", "!ping").await;
+
+    let stats = bot.send("viewer", "!cmdstats hello").await;
+    assert_eq!(stats.len(), 1);
+    assert!(stats[0].starts_with("hello was used 2 times, last by @other on "));
+    assert!(stats[0].contains("Created on "));
+
+    let stats = bot.send("viewer", "!cmdstats ping").await;
+    assert!(stats[0].starts_with("ping was used 1 time, last by @viewer"));
+    assert!(stats[0].ends_with("It is a built-in command."));
+
+    bot.send("viewer", "!cmds").await;
+    let stats = bot.send("viewer", "!cmdstats cmds").await;
+    assert!(stats[0].starts_with("commands was used 1 time, last by @viewer"));
+
+    bot.send(OWNER, "!alias add hi hello").await;
+    bot.send("viewer", "!hi").await;
+    let stats = bot.send("viewer", "!cmdstats hi").await;
+    assert!(stats[0].starts_with("hello was used 3 times, last by @viewer"));
+
+    assert_eq!(
+        bot.send("viewer", "!cmdstats nothing").await,
+        vec!["Command nothing does not exist."]
+    );
+}
+
+#[tokio::test]
+async fn builtins_answer_to_their_aliases() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send("viewer", "!cmds").await,
+        bot.send("viewer", "!commands").await
+    );
+    assert_eq!(
+        bot.send(OWNER, "!addcmd comandos Oops").await,
+        vec!["The command you are trying to add already exists as a built-in command."]
+    );
+}
+
+#[tokio::test]
+async fn custom_commands_can_be_aliased() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd discord Join us at {arg1:discord.gg}")
+        .await;
+
+    assert_eq!(
+        bot.send(OWNER, "!alias add dc discord").await,
+        vec!["dc now runs discord."]
+    );
+    assert_eq!(
+        bot.send("viewer", "!dc").await,
+        vec!["Join us at discord.gg"]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!addcmd dc Something else").await,
+        vec!["dc is an alias of discord. Remove it with alias del first."]
+    );
+
+    bot.send(OWNER, "!addcmd --global rules Be nice").await;
+    assert_eq!(
+        bot.send(OWNER, "!alias --global add r rules").await,
+        vec!["r now runs rules."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!addcmd --global r Something else").await,
+        vec!["r is an alias of rules. Remove it with alias del first."]
+    );
+
+    assert_eq!(
+        bot.send(OWNER, "!alias add ping discord").await,
+        vec!["ping is already a built-in command."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!alias add cmds discord").await,
+        vec!["cmds is already a built-in command."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!alias add x missing").await,
+        vec!["Command missing does not exist."]
+    );
+
+    assert_eq!(
+        bot.send(OWNER, "!alias del dc").await,
+        vec!["Alias deleted!"]
+    );
+    assert!(bot.send("viewer", "!dc").await.is_empty());
+}
+
+#[tokio::test]
+async fn vandalised_commands_can_be_reverted() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!trust helper").await;
+    bot.send(OWNER, "!addcmd rules Be nice").await;
+    bot.send("helper", "!updcmd rules lol").await;
+
+    let history = bot.send(OWNER, "!cmdhistory rules").await;
+    assert_eq!(history.len(), 1);
+    assert!(history[0].starts_with("rules: #2 update by helper at "));
+    assert!(history[0].contains("\"lol\" | #1 add by owner at "));
+
+    assert_eq!(
+        bot.send(OWNER, "!revertcmd rules").await,
+        vec!["rules was reverted to revision #1."]
+    );
+    assert_eq!(bot.send("viewer", "!rules").await, vec!["Be nice"]);
+
+    assert_eq!(
+        bot.send(OWNER, "!revertcmd rules 2").await,
+        vec!["rules was reverted to revision #2."]
+    );
+    assert_eq!(bot.send("viewer", "!rules").await, vec!["lol"]);
+    assert_eq!(
+        bot.send(OWNER, "!revertcmd rules 9").await,
+        vec!["rules has no revision #9."]
+    );
+
+    let history = bot.send(OWNER, "!cmdhistory rules").await;
+    assert!(history[0].starts_with("rules: #4 revert by owner at "));
+}
+
+#[tokio::test]
+async fn reverting_skips_over_deletions() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd hug Hugs").await;
+    bot.send(OWNER, "!delcmd hug").await;
+    bot.send(OWNER, "!addcmd hug Squeezes").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!revertcmd hug").await,
+        vec!["hug was reverted to revision #1."]
+    );
+    assert_eq!(bot.send("viewer", "!hug").await, vec!["Hugs"]);
+}
+
+#[tokio::test]
+async fn deleted_commands_can_be_added_again() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd rules Be nice").await;
+    bot.send(OWNER, "!cmd role rules mod").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!delcmd rules").await,
+        vec!["Command deleted!"]
+    );
+    assert!(bot.send("viewer", "!rules").await.is_empty());
+    assert_eq!(
+        bot.send(OWNER, "!addcmd rules Be kind").await,
+        vec!["Command added!"]
+    );
+    assert_eq!(bot.send("viewer", "!rules").await, vec!["Be kind"]);
+}
+
+#[tokio::test]
+async fn deleted_commands_can_be_restored() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd rules Be nice").await;
+    bot.send(OWNER, "!delcmd rules").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!updcmd rules Be mean").await,
+        vec!["Command rules does not exist. Use addcmd to create it."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!delcmd rules").await,
+        vec!["Command rules does not exist."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!undelcmd rules").await,
+        vec!["Command restored!"]
+    );
+    assert_eq!(bot.send("viewer", "!rules").await, vec!["Be nice"]);
+    assert_eq!(
+        bot.send(OWNER, "!undelcmd rules").await,
+        vec!["Command rules is not deleted."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!undelcmd nothing").await,
+        vec!["There is no deleted command called nothing."]
+    );
+}
+
+#[tokio::test]
+async fn commands_can_rotate_through_responses() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd tip Drink water").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!cmd addresp tip Stretch a bit").await,
+        vec!["Added response #2 to tip."]
+    );
+    bot.send(OWNER, "!cmd addresp tip Hi {sender}").await;
+    assert_eq!(
+        bot.send(OWNER, "!cmd mode tip rotate").await,
+        vec!["tip now picks responses in rotate mode."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!cmd listresp tip").await,
+        vec!["tip (rotate): #1: Drink water | #2: Stretch a bit | #3: Hi {sender}"]
+    );
+
+    assert_eq!(bot.send("viewer", "!tip").await, vec!["Drink water"]);
+    assert_eq!(bot.send("viewer", "!tip").await, vec!["Stretch a bit"]);
+    assert_eq!(bot.send("viewer", "!tip").await, vec!["Hi viewer"]);
+    assert_eq!(bot.send("viewer", "!tip").await, vec!["Drink water"]);
+
+    assert_eq!(
+        bot.send(OWNER, "!cmd delresp tip 2").await,
+        vec!["Deleted response #2 of tip."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!cmd delresp tip 1").await,
+        vec!["Response #1 is the main response of tip. Use updcmd to change it."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!cmd delresp tip 5").await,
+        vec!["tip has no response #5."]
+    );
+}
+
+#[tokio::test]
+async fn adding_an_existing_command_keeps_its_other_responses() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd tip Drink water").await;
+    bot.send(OWNER, "!cmd addresp tip Stretch a bit").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!addcmd tip Sleep well").await,
+        vec!["Command already exists. It was updated with the new response."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!cmd listresp tip").await,
+        vec!["tip (random): #1: Sleep well | #2: Stretch a bit"]
+    );
+
+    let history = bot.send(OWNER, "!cmdhistory tip").await;
+    assert!(history[0].starts_with("tip: #2 update by owner at "));
+}
+
+#[tokio::test]
+async fn random_picks_repeat_under_the_harness_seed() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd dice {random:1|2|3|4|5|6|7|8|9|10|11|12}")
+        .await;
+    bot.send(OWNER, "!addcmd tip Drink water").await;
+    for tip in ["Stretch", "Sleep", "Eat", "Walk", "Rest", "Read"] {
+        bot.send(OWNER, &format!("!cmd addresp tip {tip}")).await;
+    }
+
+    let dice = bot.send("viewer", "!dice").await;
+    let tip = bot.send("viewer", "!tip").await;
+    for _ in 0..5 {
+        assert_eq!(bot.send("viewer", "!dice").await, dice);
+        assert_eq!(bot.send("viewer", "!tip").await, tip);
+    }
+}
+
+#[tokio::test]
+async fn weighted_responses_follow_their_weights() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd coin heads").await;
+    bot.send(OWNER, "!cmd addresp coin tails").await;
+    bot.send(OWNER, "!cmd mode coin weighted").await;
+
+    assert_eq!(
+        bot.send(OWNER, "!cmd weight coin 1 0").await,
+        vec!["Response #1 of coin now has a weight of 0."]
+    );
+    for _ in 0..5 {
+        assert_eq!(bot.send("viewer", "!coin").await, vec!["tails"]);
+    }
+}
+
+#[tokio::test]
+async fn macros_run_other_commands() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd hello Hello {sender}").await;
+    bot.send(OWNER, "!addcmd echo {args}").await;
+    bot.send(OWNER, "!addcmd both {cmd:ping} {cmd:hello}").await;
+    bot.send(OWNER, "!addcmd intro Bot says: {cmd:hello}!")
+        .await;
+    bot.send(OWNER, "!addcmd shout {cmd:echo {arg1:hey}}").await;
+
+    assert_eq!(
+        bot.send("viewer", "!both").await,
+        vec!["Pong!", "Hello viewer"]
+    );
+    assert_eq!(
+        bot.send("viewer", "!intro").await,
+        vec!["Bot says: Hello viewer!"]
+    );
+    assert_eq!(bot.send("viewer", "!shout").await, vec!["hey"]);
+    assert_eq!(bot.send("viewer", "!shout yo").await, vec!["yo"]);
+    assert_eq!(
+        bot.send("viewer", "!echo {cmd:ping}").await,
+        vec!["{cmd:ping}"]
+    );
+}
+
+#[tokio::test]
+async fn macros_run_with_the_callers_permissions() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd sneaky {cmd:trust viewer}").await;
+
+    assert_eq!(
+        bot.send("viewer", "!sneaky").await,
+        vec!["@viewer you need the trusted role to run this command."]
+    );
+    assert_eq!(
+        bot.send("viewer", "!addcmd x y").await,
+        vec!["@viewer you need the trusted role to run this command."]
+    );
+}
+
+#[tokio::test]
+async fn recursive_macros_stop() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd loop again {cmd:loop}").await;
+
+    assert_eq!(
+        bot.send("viewer", "!loop").await,
+        vec!["again again again again"]
+    );
+}
+
+#[tokio::test]
+async fn macros_per_message_are_capped() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd leaf x").await;
+    bot.send(
+        OWNER,
+        "!addcmd branch {cmd:leaf}{cmd:leaf}{cmd:leaf}{cmd:leaf}",
+    )
+    .await;
+    bot.send(
+        OWNER,
+        "!addcmd tree {cmd:branch}{cmd:branch}{cmd:branch}{cmd:branch}",
+    )
+    .await;
+
+    assert_eq!(bot.send("viewer", "!tree").await, vec!["xxxxxxxx"]);
+    assert_eq!(bot.send("viewer", "!branch").await, vec!["xxxx"]);
+}
+
+#[tokio::test]
+async fn typos_get_a_suggestion_when_enabled() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.trusted_users = vec![String::from(OWNER)];
+    config.suggestions = true;
+    let bot = Harness::with_config(config);
+    bot.send(OWNER, "!addcmd discord Join us").await;
+
+    assert_eq!(
+        bot.deliver("viewer", "!wtr").await,
+        vec![Sent::Reply {
+            channel: String::from(CHANNEL),
+            text: String::from("@viewer did you mean !wttr?")
+        }]
+    );
+    assert!(bot.send("viewer", "!discrod").await.is_empty());
+    assert_eq!(
+        bot.send("other", "!discrod").await,
+        vec!["@other did you mean !discord?"]
+    );
+    assert!(bot.send("third", "!addcm").await.is_empty());
+    assert!(bot.send("third", "!somethingelse").await.is_empty());
+}
+
+#[tokio::test]
+async fn help_describes_builtins() {
+    let bot = Harness::new();
+
+    assert_eq!(
+        bot.send("viewer", "!help ping").await,
+        vec!["!ping — Checks that the bot is alive."]
+    );
+    assert_eq!(
+        bot.send("viewer", "!help delcmd").await,
+        vec!["!delcmd [--global] <name> — Deletes a custom command. It can be brought back with undelcmd. Requires trusted. Examples: !delcmd hug"]
+    );
+    assert_eq!(
+        bot.send("viewer", "!help !cmds").await,
+        bot.send("viewer", "!help commands").await
+    );
+    assert_eq!(
+        bot.send("viewer", "!help").await,
+        vec!["@viewer USAGE: help <command>"]
+    );
+}
+
+#[tokio::test]
+async fn help_describes_custom_commands() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd discord Join us").await;
+    bot.send(OWNER, "!alias add dc discord").await;
+
+    assert_eq!(
+        bot.send("viewer", "!help dc").await,
+        vec!["!discord is a custom command."]
+    );
+    assert_eq!(
+        bot.send(OWNER, "!cmd describe discord Link to our server")
+            .await,
+        vec!["discord now has a description."]
+    );
+    bot.send(OWNER, "!cmd role discord sub").await;
+    assert_eq!(
+        bot.send("viewer", "!help discord").await,
+        vec!["!discord — Link to our server Requires subscriber."]
+    );
+    assert_eq!(
+        bot.send("viewer", "!help nothing").await,
+        vec!["Command nothing does not exist."]
+    );
+}
+
+#[tokio::test]
+async fn commands_lists_what_the_caller_can_run() {
+    let bot = Harness::new();
+    bot.send(OWNER, "!addcmd discord Join us").await;
+    bot.send(OWNER, "!addcmd secret Shh").await;
+    bot.send(OWNER, "!cmd role secret mod").await;
+
+    let everyone = bot.send("viewer", "!commands").await;
+    assert!(everyone[0].contains("ping"));
+    assert!(everyone[0].contains("discord"));
+    assert!(!everyone[0].contains("addcmd"));
+    assert!(!everyone[0].contains("secret"));
+
+    let names: Vec<&str> = everyone[0].split(", ").collect();
+    let mut sorted = names.clone();
+    sorted.sort_by_key(|name| name.to_lowercase());
+    assert_eq!(names, sorted);
+
+    let trusted = bot.send(OWNER, "!commands").await;
+    assert!(trusted[0].contains("addcmd"));
+    assert!(trusted[0].contains("secret"));
+
+    assert_eq!(bot.send("viewer", "!commands disc").await, vec!["discord"]);
+    assert_eq!(
+        bot.send("viewer", "!commands nothing").await,
+        vec!["No commands match nothing."]
+    );
+}
+
+#[tokio::test]
+async fn long_command_lists_are_paginated() {
+    let bot = Harness::new();
+    for n in 0..40 {
+        bot.send(OWNER, &format!("!addcmd mtgcommand{n:02} Card"))
+            .await;
+    }
+
+    let first = bot.send("viewer", "!commands mtg").await;
+    assert!(first[0].starts_with("mtgcommand00, mtgcommand01"));
+    assert!(first[0].ends_with("(page 1/2)"));
+    assert!(first[0].chars().count() <= MAX_TWITCH_CHAT_MESSAGE_LENGTH);
+
+    let second = bot.send("viewer", "!commands mtg 2").await;
+    assert!(second[0].ends_with("mtgcommand39 (page 2/2)"));
+    assert_eq!(bot.send("viewer", "!commands 2 mtg").await, second);
+    assert_eq!(
+        bot.send("viewer", "!commands mtg 3").await,
+        vec!["There are only 2 page(s) of commands."]
+    );
+}
+
+#[tokio::test]
+async fn commands_can_link_to_a_generated_list() {
+    let lists = TempDir::new().unwrap();
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.trusted_users = vec![String::from(OWNER)];
+    config.list_url = Some(String::from("https://example.com/{channel}.html"));
+    config.list_path = Some(
+        lists
+            .path()
+            .join("{channel}.html")
+            .to_string_lossy()
+            .into_owned(),
+    );
+    let bot = Harness::with_config(config);
+    let page = lists.path().join("testchannel.html");
+
+    bot.send(OWNER, "!addcmd discord Join us").await;
+    bot.send(OWNER, "!cmd describe discord Our <server>").await;
+    assert_eq!(
+        bot.send("viewer", "!commands").await,
+        vec!["Commands for this channel: https://example.com/testchannel.html"]
+    );
+    let html = fs::read_to_string(&page).unwrap();
+    assert!(html.contains("<tr><td>!discord</td><td>everyone</td><td>Our &lt;server&gt;</td></tr>"));
+    assert!(html.contains("<tr><td>!addcmd</td><td>trusted</td>"));
+    assert!(html.find("!addcmd").unwrap() < html.find("!discord").unwrap());
+
+    bot.send(OWNER, "!addcmd rules Be nice").await;
+    assert!(fs::read_to_string(&page).unwrap().contains("!rules"));
+    bot.send(OWNER, "!delcmd rules").await;
+    assert!(!fs::read_to_string(&page).unwrap().contains("!rules"));
+
+    assert_eq!(bot.send("viewer", "!commands pin").await, vec!["ping"]);
+}
+
+#[tokio::test]
+async fn commands_lists_in_chat_without_a_list_path() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.list_url = Some(String::from("https://example.com/{channel}"));
+    let bot = Harness::with_config(config);
+
+    assert!(bot.send("viewer", "!commands").await[0].contains("ping"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_commands_share_the_store() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.trusted_users = vec![String::from(OWNER)];
+    let bot = Harness::on_disk(config);
+    bot.send(OWNER, "!addcmd discord Join us ({count})").await;
+
+    let replies = tokio::join!(
+        bot.send("viewer", "!discord"),
+        bot.send(OWNER, "!discord"),
+        bot.send("viewer", "!discord"),
+        bot.send(OWNER, "!discord"),
+        bot.send("viewer", "!discord"),
+        bot.send(OWNER, "!discord"),
+        bot.send("viewer", "!discord"),
+        bot.send(OWNER, "!discord"),
+    );
+
+    let mut replies = [
+        replies.0, replies.1, replies.2, replies.3, replies.4, replies.5, replies.6, replies.7,
+    ]
+    .concat();
+    replies.sort();
+    replies.dedup();
+    assert_eq!(replies.len(), 8);
+}
+
+#[tokio::test]
+async fn database_failures_get_a_friendly_reply() {
+    let mut config = config::default();
+    config.channels = vec![String::from(CHANNEL)];
+    config.trusted_users = vec![String::from(OWNER)];
+    let bot = Harness::on_disk(config);
+    bot.send(OWNER, "!addcmd discord Join us").await;
+    bot.raw_database()
+        .execute("DROP TABLE command_revisions", [])
+        .unwrap();
+
+    assert_eq!(
+        bot.send(OWNER, "!updcmd discord Changed").await,
+        vec![
+            "@owner Something went wrong with the database, so the change may not have been saved."
+        ]
+    );
+    assert_eq!(bot.send("viewer", "!discord").await, vec!["Join us"]);
+    assert_eq!(
+        bot.send(OWNER, "!cmdhistory discord").await,
+        vec!["@owner Something went wrong with the database, so the lookup failed."]
+    );
+}
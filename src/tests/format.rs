@@ -0,0 +1,59 @@
+use crate::helpers::{split_message, truncate, MAX_TWITCH_CHAT_MESSAGE_LENGTH};
+
+fn words(count: usize) -> String {
+    (0..count)
+        .map(|i| format!("word{i:03}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn short_text_is_untouched() {
+    assert_eq!(split_message("hello chat", 3), vec!["hello chat"]);
+}
+
+#[test]
+fn long_text_is_split_on_word_boundaries() {
+    let text = words(120);
+    let parts = split_message(&text, 3);
+
+    assert_eq!(parts.len(), 2);
+    assert!(parts[0].ends_with(" (1/2)"));
+    assert!(parts[1].ends_with(" (2/2)"));
+    assert!(parts
+        .iter()
+        .all(|part| part.chars().count() <= MAX_TWITCH_CHAT_MESSAGE_LENGTH));
+
+    let rejoined: Vec<&str> = parts
+        .iter()
+        .map(|part| part.rsplit_once(" (").unwrap().0)
+        .collect();
+    assert_eq!(rejoined.join(" "), text);
+}
+
+#[test]
+fn overflow_is_truncated_with_an_ellipsis() {
+    let parts = split_message(&words(500), 2);
+
+    assert_eq!(parts.len(), 2);
+    assert!(parts[1].ends_with("… (2/2)"));
+    assert!(parts[1].chars().count() <= MAX_TWITCH_CHAT_MESSAGE_LENGTH);
+}
+
+#[test]
+fn characters_are_counted_not_bytes() {
+    let text = "é".repeat(400);
+
+    assert_eq!(split_message(&text, 3), vec![text.clone()]);
+    assert_eq!(truncate(&text, 10).chars().count(), 10);
+}
+
+#[test]
+fn a_single_part_is_truncated_without_numbering() {
+    let parts = split_message(&words(120), 1);
+
+    assert_eq!(parts.len(), 1);
+    assert!(parts[0].ends_with('…'));
+    assert!(parts[0].chars().count() <= MAX_TWITCH_CHAT_MESSAGE_LENGTH);
+    assert!(!parts[0].contains("(1/"));
+}
@@ -0,0 +1,155 @@
+use rusqlite::Connection;
+use tempfile::TempDir;
+
+use crate::services::database::error::StoreError;
+use crate::services::database::migrations;
+use crate::services::database::sqlite::{SqliteStore, IN_MEMORY};
+use crate::services::database::store::{CommandStore, TrustStore};
+
+#[test]
+fn fresh_databases_reach_the_latest_version() {
+    let database = TempDir::new().unwrap();
+    let store = SqliteStore::new(database.path().join("rusted.db")).unwrap();
+
+    assert_eq!(
+        store.pending_migrations().unwrap().len(),
+        migrations::MIGRATIONS.len()
+    );
+    store.migrate(Some("chan")).unwrap();
+    assert!(store.pending_migrations().unwrap().is_empty());
+
+    store.migrate(Some("chan")).unwrap();
+    store
+        .create_command("hug", "hugs", Some("chan"), "owner")
+        .unwrap();
+    assert!(store.get_command("hug", "chan").unwrap().is_some());
+}
+
+#[test]
+fn dry_runs_do_not_touch_the_database() {
+    let database = TempDir::new().unwrap();
+    let path = database.path().join("rusted.db");
+    let store = SqliteStore::new(&path).unwrap();
+
+    store.pending_migrations().unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    let tables: i64 = connection
+        .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tables, 0);
+}
+
+#[test]
+fn unversioned_databases_are_upgraded() {
+    let database = TempDir::new().unwrap();
+    let path = database.path().join("rusted.db");
+
+    let connection = Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "
+            CREATE TABLE commands (
+                id INTEGER PRIMARY KEY,
+                name TEXT UNIQUE NOT NULL,
+                response TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                deleted_at DATETIME
+            );
+            INSERT INTO commands (name, response, created_at, updated_at)
+            VALUES ('discord', 'Join us', datetime('now'), datetime('now'));
+            ",
+        )
+        .unwrap();
+
+    let store = SqliteStore::new(&path).unwrap();
+    store.migrate(Some("chan")).unwrap();
+
+    let command = store.get_command("discord", "chan").unwrap().unwrap();
+    assert_eq!(command.responses[0].text, "Join us");
+    assert!(store.get_command("discord", "other").unwrap().is_none());
+}
+
+#[test]
+fn unscoped_trusted_users_move_to_the_default_channel() {
+    let database = TempDir::new().unwrap();
+    let path = database.path().join("rusted.db");
+
+    let store = SqliteStore::new(&path).unwrap();
+    store.migrate(Some("chan")).unwrap();
+
+    let connection = Connection::open(&path).unwrap();
+    connection
+        .execute_batch(
+            "
+            DELETE FROM schema_version WHERE version = 10;
+            DROP TABLE trusted_users;
+            CREATE TABLE trusted_users (
+                id INTEGER PRIMARY KEY,
+                username TEXT UNIQUE NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL,
+                deleted_at DATETIME
+            );
+            INSERT INTO trusted_users (username, created_at, updated_at)
+            VALUES ('helper', datetime('now'), datetime('now'));
+            ",
+        )
+        .unwrap();
+
+    store.migrate(Some("chan")).unwrap();
+
+    assert!(store.is_trusted("helper", "chan").unwrap());
+    assert!(!store.is_trusted("helper", "other").unwrap());
+}
+
+#[test]
+fn newer_databases_are_refused() {
+    let database = TempDir::new().unwrap();
+    let store = SqliteStore::new(database.path().join("rusted.db")).unwrap();
+    store.migrate(None).unwrap();
+
+    let connection = Connection::open(database.path().join("rusted.db")).unwrap();
+    connection
+        .execute(
+            "INSERT INTO schema_version VALUES (?1, 'from the future', datetime('now'))",
+            [migrations::latest_version() + 1],
+        )
+        .unwrap();
+
+    assert!(matches!(
+        store.migrate(None),
+        Err(StoreError::SchemaTooNew { .. })
+    ));
+    assert!(matches!(
+        store.pending_migrations(),
+        Err(StoreError::SchemaTooNew { .. })
+    ));
+}
+
+#[test]
+fn in_memory_databases_live_as_long_as_the_store() {
+    let store = SqliteStore::new(IN_MEMORY).unwrap();
+    store.migrate(None).unwrap();
+    store.create_command("hug", "hugs", None, "owner").unwrap();
+    store.trust_user("owner", "chan").unwrap();
+
+    assert!(store.is_trusted("owner", "chan").unwrap());
+    assert!(store.get_command("hug", "chan").unwrap().is_some());
+
+    let other = SqliteStore::new(IN_MEMORY).unwrap();
+    assert_eq!(
+        other.pending_migrations().unwrap().len(),
+        migrations::MIGRATIONS.len()
+    );
+}
+
+#[test]
+fn missing_directories_are_created() {
+    let database = TempDir::new().unwrap();
+    let path = database.path().join("nested").join("rusted.db");
+
+    SqliteStore::new(&path).unwrap().migrate(None).unwrap();
+    assert!(path.exists());
+}
@@ -0,0 +1,184 @@
+// End-to-end harness that drives chat lines through the same dispatcher the
+// bot uses in production, backed by a fake transport and a throwaway database.
+
+mod commands;
+mod config_file;
+mod dispatch;
+mod format;
+mod migrations;
+mod outbound;
+mod template;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tempfile::TempDir;
+use twitch_irc::message::{IRCMessage, ServerMessage, TwitchUserBasics};
+
+use crate::commands::context::Services;
+use crate::config::{self, Config};
+use crate::services::database::sqlite::{SqliteStore, IN_MEMORY};
+use crate::services::database::store::Store;
+use crate::services::transport::{console, ChatTransport};
+use crate::services::twitch::irc;
+
+pub const CHANNEL: &str = "testchannel";
+pub const OWNER: &str = "owner";
+
+/// Fixes random picks, such as which greeting the bot sends, so replies are
+/// the same on every run.
+const SEED: u64 = 0;
+
+/// A message the bot sent through the fake transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sent {
+    Say { channel: String, text: String },
+    Reply { channel: String, text: String },
+    Whisper { user: String, text: String },
+}
+
+impl Sent {
+    pub fn text(&self) -> &str {
+        match self {
+            Sent::Say { text, .. } | Sent::Reply { text, .. } | Sent::Whisper { text, .. } => text,
+        }
+    }
+}
+
+#[derive(Default)]
+struct FakeTransport {
+    sent: Mutex<Vec<Sent>>,
+    /// Makes every whisper fail, as Helix does without a token.
+    failing_whispers: AtomicBool,
+}
+
+impl FakeTransport {
+    fn record(&self, sent: Sent) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(sent);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatTransport for FakeTransport {
+    async fn say(&self, channel: &str, text: &str) -> anyhow::Result<()> {
+        self.record(Sent::Say {
+            channel: String::from(channel),
+            text: String::from(text),
+        })
+    }
+
+    async fn reply(&self, channel: &str, _message_id: &str, text: &str) -> anyhow::Result<()> {
+        self.record(Sent::Reply {
+            channel: String::from(channel),
+            text: String::from(text),
+        })
+    }
+
+    async fn whisper(&self, user: &TwitchUserBasics, text: &str) -> anyhow::Result<()> {
+        if self.failing_whispers.load(Ordering::SeqCst) {
+            anyhow::bail!("whispers are unavailable");
+        }
+
+        self.record(Sent::Whisper {
+            user: user.login.clone(),
+            text: String::from(text),
+        })
+    }
+}
+
+pub struct Harness {
+    pub services: Arc<Services>,
+    transport: Arc<FakeTransport>,
+    _database: Option<TempDir>,
+}
+
+impl Harness {
+    /// A bot joined to `CHANNEL` with `OWNER` as its only trusted user.
+    pub fn new() -> Harness {
+        let mut config = config::default();
+        config.channels = vec![String::from(CHANNEL)];
+        config.trusted_users = vec![String::from(OWNER)];
+
+        Harness::with_config(config)
+    }
+
+    /// A bot with `config` and a fresh in-memory database.
+    pub fn with_config(mut config: Config) -> Harness {
+        config.database_path = String::from(IN_MEMORY);
+        Harness::start(config, None)
+    }
+
+    /// A bot with `config` and a database file in a temporary directory.
+    pub fn on_disk(mut config: Config) -> Harness {
+        let database = TempDir::new().unwrap();
+        config.database_path = database.path().join("rusted.db").display().to_string();
+        Harness::start(config, Some(database))
+    }
+
+    fn start(config: Config, database: Option<TempDir>) -> Harness {
+        let store = SqliteStore::new(&config.database_path).unwrap();
+
+        store
+            .migrate(config.channels.first().map(String::as_str))
+            .unwrap();
+
+        Harness {
+            services: Arc::new(Services::new(Store::new(store), config).seeded(SEED)),
+            transport: Arc::new(FakeTransport::default()),
+            _database: database,
+        }
+    }
+
+    /// Makes every whisper the bot sends from now on fail.
+    pub fn fail_whispers(&self) {
+        self.transport
+            .failing_whispers
+            .store(true, Ordering::SeqCst);
+    }
+
+    /// Opens a separate connection to the bot's database, for tests that
+    /// need to break it.
+    pub fn raw_database(&self) -> rusqlite::Connection {
+        rusqlite::Connection::open(&self.services.config.database_path).unwrap()
+    }
+
+    /// Sends `text` as `user` in `CHANNEL` and returns the text of every
+    /// message the bot sent back, however it was delivered.
+    pub async fn send(&self, user: &str, text: &str) -> Vec<String> {
+        self.send_in(CHANNEL, user, text).await
+    }
+
+    pub async fn send_in(&self, channel: &str, user: &str, text: &str) -> Vec<String> {
+        let message = console::privmsg(channel, user, &[], text).unwrap();
+        texts(self.dispatch(message).await)
+    }
+
+    /// Sends `text` as `user` wearing chat `badges`, such as `moderator`.
+    pub async fn send_with_badges(&self, user: &str, badges: &[&str], text: &str) -> Vec<String> {
+        let message = console::privmsg(CHANNEL, user, badges, text).unwrap();
+        texts(self.dispatch(message).await)
+    }
+
+    /// Like `send`, but keeps how each message was delivered.
+    pub async fn deliver(&self, user: &str, text: &str) -> Vec<Sent> {
+        self.dispatch(console::privmsg(CHANNEL, user, &[], text).unwrap())
+            .await
+    }
+
+    /// Injects a raw IRC line, tags included, and returns what the bot sent.
+    pub async fn inject(&self, line: &str) -> Vec<Sent> {
+        let message = ServerMessage::try_from(IRCMessage::parse(line).unwrap()).unwrap();
+        self.dispatch(message).await
+    }
+
+    async fn dispatch(&self, message: ServerMessage) -> Vec<Sent> {
+        irc::handle_server_message(message, self.transport.clone(), self.services.clone()).await;
+        std::mem::take(&mut *self.transport.sent.lock().unwrap())
+    }
+}
+
+fn texts(sent: Vec<Sent>) -> Vec<String> {
+    sent.iter().map(|sent| String::from(sent.text())).collect()
+}
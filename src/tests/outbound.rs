@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time;
+use twitch_irc::message::{IRCMessage, ServerMessage};
+
+use super::{FakeTransport, CHANNEL};
+use crate::services::transport::outbound::Outbound;
+use crate::services::transport::{ChatTransport, Priority};
+
+fn outbound() -> (Outbound, Arc<FakeTransport>) {
+    let fake = Arc::new(FakeTransport::default());
+    (Outbound::new(fake.clone()), fake)
+}
+
+fn sent(fake: &FakeTransport) -> usize {
+    fake.sent.lock().unwrap().len()
+}
+
+fn server_message(line: &str) -> ServerMessage {
+    ServerMessage::try_from(IRCMessage::parse(line).unwrap()).unwrap()
+}
+
+#[tokio::test(start_paused = true)]
+async fn paces_messages_to_the_rate_limit() {
+    let (outbound, fake) = outbound();
+
+    for i in 0..25 {
+        outbound
+            .say(CHANNEL, &format!("message {i}"))
+            .await
+            .unwrap();
+    }
+
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(sent(&fake), 20);
+
+    time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(sent(&fake), 25);
+}
+
+#[tokio::test(start_paused = true)]
+async fn moderators_get_a_higher_limit() {
+    let (outbound, fake) = outbound();
+    outbound.observe(&server_message(
+        "@badge-info=;badges=moderator/1;color=;display-name=bot;emote-sets=0;mod=1;subscriber=0;user-type=mod :tmi.twitch.tv USERSTATE #testchannel",
+    ));
+
+    for i in 0..25 {
+        outbound
+            .say(CHANNEL, &format!("message {i}"))
+            .await
+            .unwrap();
+    }
+
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(sent(&fake), 25);
+}
+
+#[tokio::test(start_paused = true)]
+async fn waits_out_slow_mode() {
+    let (outbound, fake) = outbound();
+    outbound.observe(&server_message(
+        "@emote-only=0;followers-only=-1;r9k=0;room-id=1;slow=10;subs-only=0 :tmi.twitch.tv ROOMSTATE #testchannel",
+    ));
+
+    outbound.say(CHANNEL, "first").await.unwrap();
+    outbound.say(CHANNEL, "second").await.unwrap();
+
+    time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(sent(&fake), 1);
+
+    time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(sent(&fake), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_mode_does_not_hold_up_other_channels() {
+    let (outbound, fake) = outbound();
+    outbound.observe(&server_message(
+        "@emote-only=0;followers-only=-1;r9k=0;room-id=1;slow=10;subs-only=0 :tmi.twitch.tv ROOMSTATE #testchannel",
+    ));
+
+    outbound.say(CHANNEL, "first").await.unwrap();
+    outbound.say(CHANNEL, "second").await.unwrap();
+    time::sleep(Duration::from_secs(1)).await;
+    outbound.say("other", "elsewhere").await.unwrap();
+
+    time::sleep(Duration::from_secs(1)).await;
+    let texts: Vec<String> = fake
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|sent| String::from(sent.text()))
+        .collect();
+    assert_eq!(texts, vec!["first", "elsewhere"]);
+
+    time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(sent(&fake), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn drops_low_priority_messages_when_behind() {
+    let (outbound, fake) = outbound();
+
+    for i in 0..20 {
+        outbound.say(CHANNEL, &format!("filler {i}")).await.unwrap();
+    }
+    time::sleep(Duration::from_secs(1)).await;
+
+    for _ in 0..5 {
+        outbound
+            .send(CHANNEL, None, "repeated", Priority::Low)
+            .await
+            .unwrap();
+    }
+    for i in 0..15 {
+        outbound
+            .send(CHANNEL, None, &format!("chatter {i}"), Priority::Low)
+            .await
+            .unwrap();
+    }
+    outbound.say(CHANNEL, "important").await.unwrap();
+
+    time::sleep(Duration::from_secs(60)).await;
+
+    let sent = fake.sent.lock().unwrap();
+    let texts: Vec<&str> = sent[20..].iter().map(|sent| sent.text()).collect();
+    assert_eq!(texts.len(), 10);
+    assert_eq!(texts[0], "important");
+    assert_eq!(texts.iter().filter(|text| **text == "repeated").count(), 0);
+    assert!(texts.ends_with(&["chatter 14"]));
+}
@@ -0,0 +1,58 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::commands::template::{self, Variables};
+
+fn render(template: &str, vars: &Variables) -> String {
+    template::render(template, vars, &mut StdRng::seed_from_u64(0))
+}
+
+fn vars(args: &str) -> Variables<'_> {
+    Variables {
+        sender: "alice",
+        channel: "testchannel",
+        args,
+        count: 7,
+    }
+}
+
+#[test]
+fn fills_in_message_details() {
+    assert_eq!(
+        render("{sender} in #{channel}, use {count}", &vars("")),
+        "alice in #testchannel, use 7"
+    );
+}
+
+#[test]
+fn positional_arguments_fall_back_to_defaults() {
+    assert_eq!(render("{arg1} {arg2:there}", &vars("hi")), "hi there");
+    assert_eq!(render("[{arg3}]", &vars("hi")), "[]");
+    assert_eq!(render("{args}", &vars("a  b c")), "a b c");
+}
+
+#[test]
+fn touser_strips_the_mention_or_uses_the_sender() {
+    assert_eq!(render("hi {touser}", &vars("@bob")), "hi bob");
+    assert_eq!(render("hi {touser}", &vars("")), "hi alice");
+}
+
+#[test]
+fn random_picks_one_option() {
+    let picked = render("{random:a|b|c}", &vars(""));
+    assert!(["a", "b", "c"].contains(&picked.as_str()));
+}
+
+#[test]
+fn time_uses_the_given_timezone() {
+    let time = render("{time:America/Sao_Paulo}", &vars(""));
+    assert_eq!(time.len(), 5);
+    assert_eq!(&time[2..3], ":");
+
+    assert_eq!(render("{time:Nowhere}", &vars("")), "{time:Nowhere}");
+}
+
+#[test]
+fn unknown_placeholders_are_kept() {
+    assert_eq!(render("{nope} {}", &vars("")), "{nope} {}");
+}